@@ -1,3 +1,4 @@
+use std::cmp;
 use std::ops::{Range, RangeTo, RangeFrom, RangeFull};
 
 use bit_vec::traits::*;
@@ -108,7 +109,11 @@ impl<'a, Base: 'a + BitVec + ?Sized> BitVec for BitSlice<'a, Base> {
     }
 
     fn get_block(&self, position: usize) -> Self::Block {
-        self.get_bits(Self::Block::mul_nbits(position), Self::Block::nbits())
+        // The last block may be only partly in the slice.
+        let start = Self::Block::mul_nbits(position);
+        let count = cmp::min(Self::Block::nbits() as u64,
+                             self.len.saturating_sub(start));
+        self.get_bits(start, count as usize)
     }
 }
 
@@ -134,7 +139,11 @@ impl<'a, Base: 'a + BitVecMut + ?Sized> BitVec for BitSliceMut<'a, Base> {
     }
 
     fn get_block(&self, position: usize) -> Self::Block {
-        self.get_bits(Self::Block::mul_nbits(position), Self::Block::nbits())
+        // The last block may be only partly in the slice.
+        let start = Self::Block::mul_nbits(position);
+        let count = cmp::min(Self::Block::nbits() as u64,
+                             self.len.saturating_sub(start));
+        self.get_bits(start, count as usize)
     }
 }
 
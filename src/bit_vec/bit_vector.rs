@@ -1,21 +1,62 @@
+use std::cmp::{self, Ordering};
 use std::fmt;
-
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign,
+               Not};
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use num_traits::cast;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize};
 #[cfg(target_pointer_width = "32")]
 use num_traits::ToPrimitive;
 
 use internal::vector_base::{VectorBase, self};
+use rank::{BitRankSupport, RankSupport};
+use select::Select1Support;
 use space_usage::SpaceUsage;
-use storage::BlockType;
+use storage::{Address, BlockType};
 use super::traits::*;
+use super::BitSlice;
 
 /// Uncompressed vector of bits.
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct BitVector<Block: BlockType = usize>(VectorBase<Block>);
+///
+/// A bit vector can optionally cache a rank directory (see
+/// [`build_rank`](#method.build_rank)), which makes its `rank1` and
+/// `select1` queries fast until the next mutation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BitVector<Block: BlockType = usize> {
+    base: VectorBase<Block>,
+    // The number of 1s before each superblock, if built and still valid.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rank: Option<Vec<u64>>,
+}
 
 impl<Block: BlockType> BitVector<Block> {
+    fn from_base(base: VectorBase<Block>) -> Self {
+        BitVector {
+            base,
+            rank: None,
+        }
+    }
+
+    // All mutation goes through here, since it invalidates the rank
+    // directory.
+    fn base_mut(&mut self) -> &mut VectorBase<Block> {
+        self.rank = None;
+        &mut self.base
+    }
+
+    fn or_block(&mut self, index: usize, bits: Block) {
+        let block = self.get_block(index);
+        self.base_mut().set_block(1, index, block | bits);
+    }
+
     /// Creates a new, empty bit vector.
     pub fn new() -> Self {
-        BitVector(VectorBase::new())
+        Self::from_base(VectorBase::new())
     }
 
     /// Creates a new, empty bit vector with space allocated for `capacity`
@@ -27,13 +68,13 @@ impl<Block: BlockType> BitVector<Block> {
     /// blocks required by the capacity (`capacity / Block::nbits()`)
     /// must fit in a `usize`.
     pub fn with_capacity(capacity: u64) -> Self {
-        BitVector(VectorBase::with_capacity(1, capacity))
+        Self::from_base(VectorBase::with_capacity(1, capacity))
     }
 
     /// Creates a new, empty bit vector with space allocated for `capacity`
     /// blocks.
     pub fn block_with_capacity(capacity: usize) -> Self {
-        BitVector(VectorBase::block_with_capacity(capacity))
+        Self::from_base(VectorBase::block_with_capacity(capacity))
     }
 
     /// Creates a new bit vector of `len` bits initialized to `value`.
@@ -48,23 +89,79 @@ impl<Block: BlockType> BitVector<Block> {
                              .expect("BitVector::with_fill: overflow");
         let block_value = if value {!Block::zero()} else {Block::zero()};
         let mut result = Self::block_with_fill(block_size, block_value);
-        result.0.truncate(1, len);
+        result.base.truncate(1, len);
         result
     }
 
     /// Creates a new bit vector of `block_len` blocks initialized to `value`.
     pub fn block_with_fill(block_len: usize, value: Block) -> Self {
-        BitVector(VectorBase::block_with_fill(1, block_len, value))
+        Self::from_base(VectorBase::block_with_fill(1, block_len, value))
+    }
+
+    /// Creates a new bit vector of `len` bits with 1s at the given
+    /// positions and 0s elsewhere.
+    ///
+    /// Positions may come in any order, but consecutive positions in
+    /// the same block are set together, so sorted input is faster.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any position is `len` or greater.
+    pub fn from_ones<I>(positions: I, len: u64) -> Self
+        where I: IntoIterator<Item = u64>
+    {
+        let mut result = Self::with_fill(len, false);
+        let mut pending: Option<(usize, Block)> = None;
+
+        for position in positions {
+            assert!(position < len, "BitVector::from_ones: out of bounds");
+
+            let address = Address::new::<Block>(position);
+            let mask = Block::nth_mask(address.bit_offset);
+
+            pending = match pending {
+                Some((index, bits)) if index == address.block_index =>
+                    Some((index, bits | mask)),
+                Some((index, bits)) => {
+                    result.or_block(index, bits);
+                    Some((address.block_index, mask))
+                }
+                None => Some((address.block_index, mask)),
+            };
+        }
+
+        if let Some((index, bits)) = pending {
+            result.or_block(index, bits);
+        }
+
+        result
+    }
+
+    /// Creates a new bit vector of `bit_len` bits holding the low bits
+    /// of `value`, least significant bit first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len > 64`.
+    pub fn from_u64_le(value: u64, bit_len: u64) -> Self {
+        assert!(bit_len <= 64, "BitVector::from_u64_le: too many bits");
+
+        let mut result = Self::with_capacity(bit_len);
+        for i in 0 .. bit_len {
+            result.push_bit(value >> i & 1 == 1);
+        }
+
+        result
     }
 
     /// How many bits the bit vector can hold without reallocating.
     pub fn capacity(&self) -> u64 {
-        self.0.capacity(1)
+        self.base.capacity(1)
     }
 
     /// How many blocks the bit vector can hold without reallocating.
     pub fn block_capacity(&self) -> usize {
-        self.0.block_capacity()
+        self.base.block_capacity()
     }
 
     /// Resizes the bit vector to the given number of elements,
@@ -85,18 +182,18 @@ impl<Block: BlockType> BitVector<Block> {
             let trailing = Block::last_block_bits(self.bit_len());
             let remaining = Block::nbits() - trailing;
             for _ in 0 .. remaining {
-                self.0.push_bit(true);
+                self.base_mut().push_bit(true);
             }
             self.block_resize(new_block_len, !Block::zero());
         }
 
-        self.0.truncate(1, new_len);
+        self.base_mut().truncate(1, new_len);
     }
 
     /// Resizes the bit vector to the given number of blocks,
     /// filling if necessary.
     pub fn block_resize(&mut self, new_len: usize, value: Block) {
-        self.0.block_resize(1, new_len, value);
+        self.base_mut().block_resize(1, new_len, value);
     }
 
     /// Reserves capacity for at least `additional` more bits to be
@@ -109,7 +206,7 @@ impl<Block: BlockType> BitVector<Block> {
     ///
     /// Panics if the number of blocks overflows a `usize`.
     pub fn reserve(&mut self, additional: u64) {
-        self.0.reserve(1, additional);
+        self.base.reserve(1, additional);
     }
 
     /// Reserves capacity for at least `additional` blocks of bits to be
@@ -122,7 +219,7 @@ impl<Block: BlockType> BitVector<Block> {
     ///
     /// Panics if the number of blocks overflows a `usize`.
     pub fn block_reserve(&mut self, additional: usize) {
-        self.0.block_reserve(additional);
+        self.base.block_reserve(additional);
     }
 
     /// Reserves capacity for at least `additional` more bits to be
@@ -135,7 +232,7 @@ impl<Block: BlockType> BitVector<Block> {
     ///
     /// Panics if the number of blocks overflows a `usize`.
     pub fn reserve_exact(&mut self, additional: u64) {
-        self.0.reserve_exact(1, additional);
+        self.base.reserve_exact(1, additional);
     }
 
     /// Reserves capacity for at least `additional` more blocks of bits to be
@@ -148,90 +245,748 @@ impl<Block: BlockType> BitVector<Block> {
     ///
     /// Panics if the number of blocks overflows a `usize`.
     pub fn block_reserve_exact(&mut self, additional: usize) {
-        self.0.block_reserve_exact(additional);
+        self.base.block_reserve_exact(additional);
     }
 
     /// Shrinks the capacity to just fit the number of elements.
+    ///
+    /// Afterward, `block_capacity()` equals `block_len()`.
     pub fn shrink_to_fit(&mut self) {
-        self.0.shrink_to_fit()
+        self.base.shrink_to_fit()
     }
 
     /// Shrinks to the given size.
     ///
     /// Does nothing if `len` is greater than the current size.
     pub fn truncate(&mut self, len: u64) {
-        self.0.truncate(1, len);
+        self.base_mut().truncate(1, len);
     }
 
     /// Shrinks to the given size in blocks.
     ///
     /// Does nothing if `block_len` is greater than the current size in blocks.
     pub fn block_truncate(&mut self, block_len: usize) {
-        self.0.block_truncate(1, block_len);
+        self.base_mut().block_truncate(1, block_len);
     }
 
     /// Sets the size to 0 while retaining the allocated storage.
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.base_mut().clear();
     }
 
     /// Returns an iterator over the bits of the bit vector
     pub fn iter(&self) -> Iter<Block> {
-        Iter(vector_base::Iter::new(1, &self.0))
+        Iter(vector_base::Iter::new(1, &self.base))
+    }
+
+    /// Returns the first bit, or `None` if the bit vector is empty.
+    pub fn first_bit(&self) -> Option<bool> {
+        if self.bit_len() == 0 {None} else {Some(self.get_bit(0))}
+    }
+
+    /// Returns the last bit, or `None` if the bit vector is empty.
+    pub fn last_bit(&self) -> Option<bool> {
+        let len = self.bit_len();
+        if len == 0 {None} else {Some(self.get_bit(len - 1))}
+    }
+
+    /// Borrows `len` bits starting at `start` as a read-only slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice extends past the end of the vector.
+    pub fn slice(&self, start: u64, len: u64) -> BitSlice<'_, Self> {
+        let end = start.checked_add(len)
+                       .expect("BitVector::slice: index overflow");
+        assert!(end <= self.bit_len(), "BitVector::slice: out of bounds");
+        BitSlice::new(self, start .. end)
+    }
+
+    /// Clears every 1 bit after the first `n`, leaving the length
+    /// unchanged.
+    ///
+    /// This finds the `n`th 1 by scanning the blocks, so it doesn’t
+    /// require a select structure. If the vector contains `n` or fewer
+    /// 1s then it is unchanged.
+    pub fn keep_first_ones(&mut self, n: u64) {
+        let mut remaining = n;
+        let mut index = 0;
+
+        while index < self.block_len() {
+            let block = self.get_block(index);
+            let ones = block.popcount() as u64;
+
+            if ones > remaining {
+                // Clear the lowest `remaining` 1s from a copy of the
+                // block to find the ones that have to go.
+                let mut excess = block;
+                for _ in 0 .. remaining {
+                    excess = excess & (excess - Block::one());
+                }
+
+                self.base_mut().set_block(1, index, block & !excess);
+                index += 1;
+                break;
+            }
+
+            remaining -= ones;
+            index += 1;
+        }
+
+        while index < self.block_len() {
+            self.base_mut().set_block(1, index, Block::zero());
+            index += 1;
+        }
+    }
+
+    /// Interprets the whole bit vector as a little-endian integer.
+    ///
+    /// Unlike `get_bits`, this requires that every bit fits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len > 64`.
+    pub fn to_u64_le(&self) -> u64 {
+        assert!(self.bit_len() <= 64, "BitVector::to_u64_le: too many bits");
+
+        (0 .. self.block_len()).fold(0, |result, i| {
+            let block = self.get_block(i).to_u64().unwrap();
+            result | block << Block::mul_nbits(i)
+        })
+    }
+
+    /// Interprets the whole bit vector as a little-endian integer.
+    ///
+    /// Unlike `get_bits`, this requires that every bit fits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_len > 128`.
+    pub fn to_u128_le(&self) -> u128 {
+        assert!(self.bit_len() <= 128, "BitVector::to_u128_le: too many bits");
+
+        (0 .. self.block_len()).fold(0, |result, i| {
+            let block = self.get_block(i).to_u128().unwrap();
+            result | block << Block::mul_nbits(i)
+        })
+    }
+
+    /// Copies the bits into a bit vector with a different block type.
+    ///
+    /// The length and every bit are preserved. Since block sizes are
+    /// powers of two, each destination block is either several whole
+    /// source blocks or a piece of one.
+    pub fn rebind<Dst: BlockType>(&self) -> BitVector<Dst> {
+        let src_bits = Block::nbits();
+        let dst_bits = Dst::nbits();
+        let dst_len = Dst::ceil_div_nbits(self.bit_len());
+
+        let mut result = BitVector::block_with_capacity(dst_len);
+
+        if dst_bits >= src_bits {
+            let ratio = dst_bits / src_bits;
+            for j in 0 .. dst_len {
+                let mut block = Dst::zero();
+                for r in 0 .. ratio {
+                    let i = j * ratio + r;
+                    if i == self.block_len() { break }
+                    let piece: Dst = cast(self.get_block(i)).unwrap();
+                    block = block | piece << (r * src_bits);
+                }
+                result.push_block(block);
+            }
+        } else {
+            let ratio = src_bits / dst_bits;
+            for j in 0 .. dst_len {
+                let block = self.get_block(j / ratio)
+                                .get_bits((j % ratio) * dst_bits, dst_bits);
+                result.push_block(cast(block).unwrap());
+            }
+        }
+
+        result.truncate(self.bit_len());
+        result
+    }
+
+    /// Writes the bit length and then each block, in byte order `T`.
+    ///
+    /// The length is written as a `u64`. Use `read_blocks` with the same
+    /// block type and byte order to read the result back.
+    pub fn write_blocks<W, T>(&self, sink: &mut W) -> io::Result<()>
+        where W: io::Write, T: ByteOrder
+    {
+        try!(sink.write_u64::<T>(self.bit_len()));
+        for i in 0 .. self.block_len() {
+            try!(self.get_block(i).write_block::<W, T>(sink));
+        }
+        Ok(())
+    }
+
+    /// Reads a bit vector written by `write_blocks`.
+    ///
+    /// It is an error if the length is too large for this platform, or
+    /// if the unused bits of the last block aren’t 0.
+    pub fn read_blocks<R, T>(source: &mut R) -> io::Result<Self>
+        where R: io::Read, T: ByteOrder
+    {
+        let invalid = |message| {
+            io::Error::new(io::ErrorKind::InvalidData,
+                           format!("BitVector::read_blocks: {}", message))
+        };
+
+        let bit_len = try!(source.read_u64::<T>());
+        let block_len = try!(Block::checked_ceil_div_nbits(bit_len)
+            .ok_or_else(|| invalid("length too large")));
+
+        let mut result = Self::new();
+        for _ in 0 .. block_len {
+            result.push_block(try!(Block::read_block::<R, T>(source)));
+        }
+
+        if block_len > 0 {
+            let last = result.get_block(block_len - 1);
+            let used = Block::last_block_bits(bit_len);
+            if last & !Block::low_mask(used) != Block::zero() {
+                return Err(invalid("nonzero padding"));
+            }
+        }
+
+        result.truncate(bit_len);
+        Ok(result)
+    }
+
+    /// Appends the bits of `other` to the end of this bit vector.
+    ///
+    /// The bits are copied a block at a time; when this vector’s length
+    /// isn’t a multiple of the block size, each block of `other` is
+    /// shifted into place.
+    pub fn append(&mut self, other: &BitVector<Block>) {
+        let shift = Block::mod_nbits(self.bit_len());
+        let full_blocks = Block::div_nbits(other.bit_len());
+
+        self.reserve(other.bit_len());
+
+        if shift == 0 {
+            self.base_mut().extend_blocks(1, (0 .. full_blocks)
+                                                 .map(|i| other.get_block(i)));
+        } else {
+            // Take off our partial last block, then fill it and each
+            // new block from two adjacent blocks of `other`.
+            let mut carry = self.base_mut().pop_block(1)
+                                .expect("BitVector::append: no last block");
+            let blocks = (0 .. full_blocks).map(|i| {
+                let block = other.get_block(i);
+                let result = carry | block << shift;
+                carry = block >> (Block::nbits() - shift);
+                result
+            });
+            self.base_mut().extend_blocks(1, blocks);
+
+            for i in 0 .. shift {
+                self.push_bit(carry.get_bit(i));
+            }
+        }
+
+        for i in Block::mul_nbits(full_blocks) .. other.bit_len() {
+            self.push_bit(other.get_bit(i));
+        }
+    }
+
+    /// Reverses the order of the bits in place.
+    pub fn reverse(&mut self) {
+        let block_len = self.block_len();
+        if block_len == 0 { return; }
+
+        // Reversing the blocks and the bits within each block moves the
+        // padding from the end of the last block to the start of the
+        // first, so then we shift everything down by that much.
+        let padding = Block::nbits() - Block::last_block_bits(self.bit_len());
+        let blocks: Vec<Block> = (0 .. block_len).rev()
+            .map(|i| self.get_block(i).reverse_bits())
+            .collect();
+
+        let base = self.base_mut();
+        for i in 0 .. block_len {
+            let mut block = blocks[i] >> padding;
+            if padding > 0 && i + 1 < block_len {
+                block = block | blocks[i + 1] << (Block::nbits() - padding);
+            }
+            base.set_block(1, i, block);
+        }
+    }
+
+    /// Counts the positions at which this bit vector and `other` differ.
+    ///
+    /// This is `(self ^ other).count_ones()` without allocating the
+    /// intermediate vector. Since the unused bits of the last block are
+    /// always 0, they never contribute a difference.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lengths differ.
+    pub fn hamming_distance(&self, other: &Self) -> u64 {
+        assert_eq!(self.bit_len(), other.bit_len(),
+                   "BitVector::hamming_distance: lengths differ");
+
+        (0 .. self.block_len())
+            .map(|i| {
+                (self.get_block(i) ^ other.get_block(i)).popcount() as u64
+            })
+            .sum()
+    }
+
+    /// Inserts a bit at position `index`, shifting the bits after it up
+    /// by one.
+    ///
+    /// This takes time linear in the number of blocks after `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > bit_len`.
+    pub fn insert_bit(&mut self, index: u64, value: bool) {
+        assert!(index <= self.bit_len(),
+                "BitVector::insert_bit: out of bounds");
+
+        self.push_bit(false);
+
+        let address = Address::new::<Block>(index);
+        let top = Block::nbits() - 1;
+
+        // Working down, each block takes the top bit of the one below.
+        for i in (address.block_index + 1 .. self.block_len()).rev() {
+            let block = self.get_block(i) << 1 | self.get_block(i - 1) >> top;
+            self.base_mut().set_block(1, i, block);
+        }
+
+        let block = self.get_block(address.block_index);
+        let low_mask = Block::low_mask(address.bit_offset);
+        let block = (block & low_mask)
+            | (block & !low_mask) << 1
+            | Block::zero().with_bit(address.bit_offset, value);
+        self.base_mut().set_block(1, address.block_index, block);
+    }
+
+    /// Removes and returns the bit at position `index`, shifting the bits
+    /// after it down by one.
+    ///
+    /// This takes time linear in the number of blocks after `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= bit_len`.
+    pub fn remove_bit(&mut self, index: u64) -> bool {
+        assert!(index < self.bit_len(),
+                "BitVector::remove_bit: out of bounds");
+
+        let result = self.get_bit(index);
+        let address = Address::new::<Block>(index);
+        let top = Block::nbits() - 1;
+        let block_len = self.block_len();
+
+        // The bottom bit of the following block, moved to the top.
+        let carry = |this: &Self, i: usize| {
+            if i + 1 < block_len {
+                this.get_block(i + 1) << top
+            } else {
+                Block::zero()
+            }
+        };
+
+        let block = self.get_block(address.block_index);
+        let low_mask = Block::low_mask(address.bit_offset);
+        let block = (block & low_mask)
+            | (block >> 1 & !low_mask)
+            | carry(self, address.block_index);
+        self.base_mut().set_block(1, address.block_index, block);
+
+        for i in address.block_index + 1 .. block_len {
+            let block = self.get_block(i) >> 1 | carry(self, i);
+            self.base_mut().set_block(1, i, block);
+        }
+
+        self.pop_bit();
+        result
+    }
+
+    /// Returns a copy with every bit moved `n` positions toward the end,
+    /// filling the start with 0s.
+    ///
+    /// Bit `i` of the result is bit `i - n` of `self`. This is `<<` if
+    /// you think of bit 0 as the least significant. The length is
+    /// unchanged, so the last `n` bits are dropped.
+    pub fn shl_bits(&self, n: u64) -> Self {
+        let mut result = Self::with_fill(self.bit_len(), false);
+        if n >= self.bit_len() { return result; }
+
+        let address = Address::new::<Block>(n);
+        let (skip, offset) = (address.block_index, address.bit_offset);
+
+        for i in skip .. self.block_len() {
+            let mut block = self.get_block(i - skip) << offset;
+            if offset > 0 && i > skip {
+                block = block
+                    | self.get_block(i - skip - 1) >> (Block::nbits() - offset);
+            }
+            result.base_mut().set_block(1, i, block);
+        }
+
+        result
+    }
+
+    /// Returns a copy with every bit moved `n` positions toward the
+    /// start, filling the end with 0s.
+    ///
+    /// Bit `i` of the result is bit `i + n` of `self`. This is `>>` if
+    /// you think of bit 0 as the least significant. The length is
+    /// unchanged, so the first `n` bits are dropped.
+    pub fn shr_bits(&self, n: u64) -> Self {
+        let mut result = Self::with_fill(self.bit_len(), false);
+        if n >= self.bit_len() { return result; }
+
+        let address = Address::new::<Block>(n);
+        let (skip, offset) = (address.block_index, address.bit_offset);
+        let block_len = self.block_len();
+
+        // The padding in the last block is 0, so it shifts in 0s.
+        for i in 0 .. block_len - skip {
+            let mut block = self.get_block(i + skip) >> offset;
+            if offset > 0 && i + skip + 1 < block_len {
+                block = block
+                    | self.get_block(i + skip + 1) << (Block::nbits() - offset);
+            }
+            result.base_mut().set_block(1, i, block);
+        }
+
+        result
+    }
+
+    /// Splits the bit vector in two at the given index.
+    ///
+    /// Returns a new bit vector containing the bits `[at, bit_len)`,
+    /// and leaves `self` containing `[0, at)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > bit_len`.
+    pub fn split_off(&mut self, at: u64) -> Self {
+        let len = self.bit_len();
+        assert!(at <= len, "BitVector::split_off: out of bounds");
+
+        let mut result = Self::with_capacity(len - at);
+        let nbits = Block::nbits();
+        let mut position = at;
+
+        // When `at` isn’t block-aligned, each block of the result
+        // straddles two blocks of `self`, which `get_bits` handles.
+        while position + nbits as u64 <= len {
+            result.push_block(self.get_bits(position, nbits));
+            position += nbits as u64;
+        }
+
+        let count = (len - position) as usize;
+        if count > 0 {
+            let start = result.bit_len();
+            result.resize(start + count as u64, false);
+            result.set_bits(start, count, self.get_bits(position, count));
+        }
+
+        self.truncate(at);
+        result
+    }
+}
+
+impl<Block: BlockType> BitVector<Block> {
+    /// Builds and caches a rank directory, making `rank1` constant-time
+    /// and `select1` logarithmic.
+    ///
+    /// The directory stores the number of 1s preceding each 512-bit
+    /// superblock as a `u64`, which adds 12.5% to the size of the bits.
+    /// A query counts the ones of at most one superblock’s blocks. Any
+    /// subsequent mutation of the bit vector discards the directory, so
+    /// queries never see stale counts; without a directory, `rank1` and
+    /// `select1` fall back to scanning the blocks.
+    pub fn build_rank(&mut self) {
+        let per_superblock = Self::blocks_per_superblock();
+        let mut counts = Vec::with_capacity(
+            self.block_len().ceil_div(per_superblock));
+        let mut total = 0;
+
+        for i in 0 .. self.block_len() {
+            if i % per_superblock == 0 {
+                counts.push(total);
+            }
+            total += self.get_block(i).popcount() as u64;
+        }
+
+        self.rank = Some(counts);
+    }
+
+    /// Is there a valid cached rank directory?
+    pub fn has_rank(&self) -> bool {
+        self.rank.is_some()
+    }
+
+    fn blocks_per_superblock() -> usize {
+        cmp::max(1, RANK_SUPERBLOCK_BITS / Block::nbits())
+    }
+
+    // The number of 1s in the blocks before `block_index`.
+    fn ones_before_block(&self, block_index: usize) -> u64 {
+        let (start, before) = match self.rank {
+            Some(ref counts) => {
+                let superblock = block_index / Self::blocks_per_superblock();
+                (superblock * Self::blocks_per_superblock(),
+                 counts[superblock])
+            }
+            None => (0, 0),
+        };
+
+        before + (start .. block_index)
+                     .map(|i| self.get_block(i).popcount() as u64)
+                     .sum::<u64>()
     }
 }
 
+// The number of bits covered by each count in the rank directory.
+const RANK_SUPERBLOCK_BITS: usize = 512;
+
 impl<Block: BlockType> BitVec for BitVector<Block> {
     type Block = Block;
 
     #[inline]
     fn bit_len(&self) -> u64 {
-        self.0.len()
+        self.base.len()
     }
 
     fn get_bit(&self, index: u64) -> bool {
-        self.0.get_bit(index)
+        self.base.get_bit(index)
     }
 
     #[inline]
     fn get_block(&self, index: usize) -> Block {
-        self.0.get_block(index)
+        self.base.get_block(index)
+    }
+
+    // The unused bits of the last block are always 0, so there’s no
+    // need to mask it.
+    fn count_ones(&self) -> u64 {
+        (0 .. self.block_len())
+            .map(|i| self.get_block(i).popcount() as u64)
+            .sum()
     }
 }
 
 impl<Block: BlockType> BitVecMut for BitVector<Block> {
     fn set_bit(&mut self, index: u64, value: bool) {
-        self.0.set_bit(index, value);
+        self.base_mut().set_bit(index, value);
     }
 
     #[inline]
     fn set_block(&mut self, index: usize, value: Block) {
-        self.0.set_block(1, index, value);
+        self.base_mut().set_block(1, index, value);
     }
 }
 
 impl<Block: BlockType> BitVecPush for BitVector<Block> {
     fn push_bit(&mut self, value: bool) {
-        self.0.push_bit(value);
+        self.base_mut().push_bit(value);
     }
 
     fn pop_bit(&mut self) -> Option<bool> {
-        self.0.pop_bit()
+        self.base_mut().pop_bit()
     }
 
     fn push_block(&mut self, value: Block) {
-        self.0.push_block(1, value);
+        self.base_mut().push_block(1, value);
+    }
+}
+
+impl<Block: BlockType> RankSupport for BitVector<Block> {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.bit_len()
+    }
+}
+
+impl<Block: BlockType> BitRankSupport for BitVector<Block> {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.bit_len(), "BitVector::rank1: out of bounds");
+
+        let address = Address::new::<Block>(position);
+        self.ones_before_block(address.block_index)
+            + self.get_block(address.block_index)
+                  .rank1(address.bit_offset as u64)
+    }
+}
+
+impl<Block: BlockType> Select1Support for BitVector<Block> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        // Start from the last superblock with at most `index` 1s before
+        // it, or from the beginning, then count forward a block at a
+        // time.
+        let (start, mut total) = match self.rank {
+            Some(ref counts) if !counts.is_empty() => {
+                let superblock =
+                    counts.partition_point(|&count| count <= index) - 1;
+                (superblock * Self::blocks_per_superblock(),
+                 counts[superblock])
+            }
+            _ => (0, 0),
+        };
+
+        for block_index in start .. self.block_len() {
+            let block = self.get_block(block_index);
+            let ones = block.popcount() as u64;
+
+            if total + ones > index {
+                let offset = block.select1((index - total) as usize)
+                                  .expect("BitVector::select1: bad count");
+                return Some(Block::mul_nbits(block_index) + offset as u64);
+            }
+
+            total += ones;
+        }
+
+        None
+    }
+}
+
+// The rank directory isn’t serialized, so it needs to be rebuilt after
+// deserializing.
+#[cfg(feature = "serde")]
+impl<'de, Block> Deserialize<'de> for BitVector<Block>
+        where Block: BlockType + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de> {
+
+        #[derive(Deserialize)]
+        #[serde(rename = "BitVector")]
+        struct Fields<Block> {
+            base: VectorBase<Block>,
+        }
+
+        let fields = try!(Fields::deserialize(deserializer));
+
+        if !fields.base.is_valid(1) {
+            return Err(de::Error::custom("BitVector: invalid data"));
+        }
+
+        Ok(BitVector {
+            base: fields.base,
+            rank: None,
+        })
+    }
+}
+
+impl<Block: BlockType> PartialEq for BitVector<Block> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base
+    }
+}
+
+impl<Block: BlockType> Eq for BitVector<Block> {}
+
+impl<Block: BlockType> PartialOrd for BitVector<Block> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Block: BlockType> Ord for BitVector<Block> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.base.cmp(&other.base)
+    }
+}
+
+impl<Block: BlockType + Hash> Hash for BitVector<Block> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.base.hash(state);
+    }
+}
+
+// The binary operators work a block at a time, and require both operands
+// to have the same length. Since both have their unused bits cleared,
+// so does the result.
+macro_rules! impl_bit_vector_op {
+    ( $op:ident, $method:ident, $op_assign:ident, $method_assign:ident,
+      $name:expr ) =>
+    {
+        impl<'a, Block: BlockType> $op_assign<&'a BitVector<Block>>
+            for BitVector<Block>
+        {
+            fn $method_assign(&mut self, other: &'a BitVector<Block>) {
+                assert_eq!(self.bit_len(), other.bit_len(),
+                           concat!("BitVector::", $name, ": lengths differ"));
+
+                for i in 0 .. self.block_len() {
+                    let block = self.get_block(i).$method(other.get_block(i));
+                    self.base_mut().set_block(1, i, block);
+                }
+            }
+        }
+
+        impl<'a, 'b, Block: BlockType> $op<&'b BitVector<Block>>
+            for &'a BitVector<Block>
+        {
+            type Output = BitVector<Block>;
+
+            fn $method(self, other: &'b BitVector<Block>) -> BitVector<Block> {
+                let mut result = self.clone();
+                result.$method_assign(other);
+                result
+            }
+        }
+    }
+}
+
+impl_bit_vector_op!(BitAnd, bitand, BitAndAssign, bitand_assign, "bitand");
+impl_bit_vector_op!(BitOr, bitor, BitOrAssign, bitor_assign, "bitor");
+impl_bit_vector_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, "bitxor");
+
+impl<Block: BlockType> Not for BitVector<Block> {
+    type Output = BitVector<Block>;
+
+    /// Flips every bit, leaving the unused bits of the last block clear.
+    fn not(mut self) -> BitVector<Block> {
+        for i in 0 .. self.block_len() {
+            let block = !self.get_block(i);
+            // `VectorBase::set_block` masks the last block.
+            self.base_mut().set_block(1, i, block);
+        }
+
+        self
+    }
+}
+
+impl<Block: BlockType> Not for &BitVector<Block> {
+    type Output = BitVector<Block>;
+
+    fn not(self) -> BitVector<Block> {
+        !self.clone()
     }
 }
 
+/// Formats the bits in index order, bit 0 first.
+///
+/// The alternate form (`{:#b}`) separates each group of 8 bits with an
+/// underscore. Width, fill, and alignment are respected.
 impl<Block: BlockType> fmt::Binary for BitVector<Block> {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        for bit in self {
-            let bit = if bit {"1"} else {"0"};
-            try!(formatter.write_str(bit));
+        let mut result = String::with_capacity(self.bit_len() as usize);
+
+        for (i, bit) in self.iter().enumerate() {
+            if formatter.alternate() && i > 0 && i & 7 == 0 {
+                result.push('_');
+            }
+            result.push(if bit {'1'} else {'0'});
         }
 
-        Ok(())
+        formatter.pad(&result)
     }
 }
 
@@ -239,7 +994,9 @@ impl<Block: BlockType> SpaceUsage for BitVector<Block> {
     fn is_stack_only() -> bool { false }
 
     fn heap_bytes(&self) -> usize {
-        self.0.heap_bytes()
+        self.base.heap_bytes() + self.rank.as_ref().map_or(0, |counts| {
+            counts.heap_bytes()
+        })
     }
 }
 
@@ -310,6 +1067,201 @@ mod test {
         }
     }
 
+    #[test]
+    fn first_last_bit() {
+        let mut bv: BitVector<u8> = BitVector::new();
+        assert_eq!(None, bv.first_bit());
+        assert_eq!(None, bv.last_bit());
+
+        bv.push_bit(true);
+        assert_eq!(Some(true), bv.first_bit());
+        assert_eq!(Some(true), bv.last_bit());
+
+        for _ in 0 .. 9 {
+            bv.push_bit(false);
+        }
+        assert_eq!(Some(true), bv.first_bit());
+        assert_eq!(Some(false), bv.last_bit());
+    }
+
+    #[test]
+    fn rebind() {
+        let mut bv32: BitVector<u32> = BitVector::new();
+        let mut bv8: BitVector<u8> = BitVector::new();
+        for i in 0 .. 150 {
+            bv32.push_bit(i % 3 == 1 || i % 17 == 0);
+            bv8.push_bit(i % 5 == 2 || i % 13 == 0);
+        }
+
+        let wide: BitVector<u64> = bv32.rebind();
+        assert_eq!(bv32.bit_len(), wide.bit_len());
+        assert_eq!(format!("{:b}", bv32), format!("{:b}", wide));
+
+        let wide: BitVector<u64> = bv8.rebind();
+        assert_eq!(bv8.bit_len(), wide.bit_len());
+        assert_eq!(format!("{:b}", bv8), format!("{:b}", wide));
+
+        let narrow: BitVector<u8> = wide.rebind();
+        assert_eq!(bv8, narrow);
+
+        let empty: BitVector<u64> = BitVector::<u8>::new().rebind();
+        assert_eq!(0, empty.bit_len());
+    }
+
+    #[test]
+    fn from_ones() {
+        let sorted: BitVector<u8> =
+            BitVector::from_ones(vec![0, 3, 4, 8, 19], 20);
+        assert_bv!("10011000100000000001", sorted);
+
+        let unsorted: BitVector<u8> =
+            BitVector::from_ones(vec![19, 4, 0, 8, 3, 4], 20);
+        assert_eq!(sorted, unsorted);
+
+        let empty: BitVector<u8> = BitVector::from_ones(None, 5);
+        assert_bv!("00000", empty);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_ones_oob() {
+        let _: BitVector = BitVector::from_ones(vec![2, 5], 5);
+    }
+
+    #[test]
+    fn u64_le() {
+        let value = 0xF00D_CAFE_1234_5678u64;
+
+        for len in 0 ..= 64 {
+            let bv: BitVector<u8> = BitVector::from_u64_le(value, len);
+            let expected = if len == 64 {value} else {value & ((1 << len) - 1)};
+
+            assert_eq!(len, bv.bit_len());
+            assert_eq!(expected, bv.to_u64_le());
+            assert_eq!(expected as u128, bv.to_u128_le());
+        }
+    }
+
+    #[test]
+    fn read_write_blocks() {
+        use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+        fn round_trip<T: ByteOrder>(bv: &BitVector<u32>) -> BitVector<u32> {
+            let mut bytes = Vec::new();
+            bv.write_blocks::<_, T>(&mut bytes).unwrap();
+            assert_eq!(8 + 4 * bv.block_len(), bytes.len());
+            BitVector::read_blocks::<_, T>(&mut &bytes[..]).unwrap()
+        }
+
+        for &len in &[0, 1, 31, 32, 33, 100] {
+            let bv: BitVector<u32> =
+                BitVector::from_ones((0 .. len).filter(|i| i % 3 == 0), len);
+            assert_eq!(bv, round_trip::<LittleEndian>(&bv));
+            assert_eq!(bv, round_trip::<BigEndian>(&bv));
+        }
+
+        let bv: BitVector<u32> = BitVector::from_ones(vec![0, 9], 40);
+        let mut le = Vec::new();
+        let mut be = Vec::new();
+        bv.write_blocks::<_, LittleEndian>(&mut le).unwrap();
+        bv.write_blocks::<_, BigEndian>(&mut be).unwrap();
+        assert_eq!(&[40, 0, 0, 0, 0, 0, 0, 0, 0b1, 0b10], &le[.. 10]);
+        assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 40, 0, 0, 0b10, 0b1], &be[.. 12]);
+    }
+
+    #[test]
+    fn read_blocks_invalid() {
+        use std::io;
+        use byteorder::LittleEndian;
+
+        // Three bits, but bit 5 of the block is set.
+        let bytes = [3, 0, 0, 0, 0, 0, 0, 0, 0b10_0001];
+        let result = BitVector::<u8>::read_blocks::<_, LittleEndian>(
+            &mut &bytes[..]);
+        assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+
+        // Missing the second block.
+        let bytes = [9, 0, 0, 0, 0, 0, 0, 0, 0xFF];
+        let result = BitVector::<u8>::read_blocks::<_, LittleEndian>(
+            &mut &bytes[..]);
+        assert_eq!(io::ErrorKind::UnexpectedEof, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn u128_blocks() {
+        use rank::BitRankSupport;
+        use select::Select1Support;
+
+        let mut bv: BitVector<u128> =
+            BitVector::from_ones(vec![0, 100, 127, 128, 200], 250);
+        assert_eq!(2, bv.block_len());
+        assert_eq!(5, bv.count_ones());
+        assert_eq!(0b11, bv.get_bits(127, 2));
+        assert_eq!(Some(200), bv.select1(4));
+        assert_eq!(4, bv.rank1(199));
+
+        bv.set_bits(120, 16, 0xABCD);
+        assert_eq!(0xABCD, bv.get_bits(120, 16));
+        assert!(bv.get_bit(128) && bv.get_bit(200));
+
+        bv.reverse();
+        assert!(bv.get_bit(249));
+        assert!(bv.get_bit(149));
+    }
+
+    #[test]
+    fn u128_le() {
+        let mut bv: BitVector<u32> = BitVector::from_u64_le(!0, 64);
+        bv.push_bit(false);
+        bv.push_bit(true);
+        assert_eq!(1 << 65 | 0xFFFF_FFFF_FFFF_FFFF, bv.to_u128_le());
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_u64_le_too_long() {
+        let bv: BitVector = BitVector::with_fill(65, false);
+        bv.to_u64_le();
+    }
+
+    #[test]
+    fn split_off() {
+        let mut original: BitVector<u8> = BitVector::new();
+        for i in 0 .. 45 {
+            original.push_bit(i % 3 == 0 || i % 7 == 2);
+        }
+
+        for at in 0 .. 46 {
+            let mut front = original.clone();
+            let back = front.split_off(at);
+
+            assert_eq!(at, front.bit_len());
+            assert_eq!(45 - at, back.bit_len());
+
+            for i in 0 .. at {
+                assert_eq!(original.get_bit(i), front.get_bit(i));
+            }
+            for i in at .. 45 {
+                assert_eq!(original.get_bit(i), back.get_bit(i - at));
+            }
+
+            // Both halves keep their unused trailing bits zero, so
+            // concatenating them reproduces the original.
+            let mut joined = front;
+            for bit in &back {
+                joined.push_bit(bit);
+            }
+            assert_eq!(original, joined);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_oob() {
+        let mut bv: BitVector<u8> = BitVector::with_fill(10, true);
+        bv.split_off(11);
+    }
+
     #[test]
     fn new() {
         let bit_vector: BitVector = BitVector::new();
@@ -335,6 +1287,19 @@ mod test {
         assert_eq!("100", format!("{:b}", bit_vector));
     }
 
+    #[test]
+    fn binary_alternate() {
+        let bv: BitVector<u8> =
+            BitVector::from_ones(vec![0, 9, 10, 19], 20);
+
+        assert_eq!("10000000011000000001", format!("{:b}", bv));
+        assert_eq!("10000000_01100000_0001", format!("{:#b}", bv));
+        assert_eq!("  10000000_01100000_0001", format!("{:>#24b}", bv));
+        assert_eq!("10000000_01100000_0001**",
+                   format!("{:*<#24b}", bv));
+        assert_eq!("", format!("{:#b}", BitVector::<u8>::new()));
+    }
+
     #[test]
     fn block_with_fill() {
         let bit_vector: BitVector<u8> = BitVector::block_with_fill(3, 0b101);
@@ -510,4 +1475,445 @@ mod test {
         bit_vector.block_resize(2, 0);
         assert_bv!("1010000010100010", bit_vector);
     }
+
+    #[test]
+    fn get_set_range() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+
+        bit_vector.set_range_bits(6 .. 11, 0b10111);
+        assert_bv!("00000011101000000000", bit_vector);
+        assert_eq!(0b10111, bit_vector.get_range(6 .. 11));
+        assert_eq!(0b1110, bit_vector.get_range(5 .. 9));
+
+        bit_vector.set_range_bits(3 .. 3, 0);
+        assert_eq!(0, bit_vector.get_range(3 .. 3));
+        assert_eq!(0, bit_vector.get_range(20 .. 20));
+        assert_bv!("00000011101000000000", bit_vector);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_range_oob() {
+        let bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+        bit_vector.get_range(15 .. 21);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_range_bits_oob() {
+        let mut bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+        bit_vector.set_range_bits(18 .. 22, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_range_inverted() {
+        let bit_vector: BitVector<u8> = BitVector::with_fill(20, false);
+        let (start, end) = (5, 3);
+        bit_vector.get_range(start .. end);
+    }
+
+    #[test]
+    fn count_ones() {
+        for len in 0 .. 40 {
+            let bv: BitVector<u8> =
+                BitVector::from_ones((0 .. len).filter(|i| i % 3 != 1), len);
+            let expected = (0 .. len).filter(|i| i % 3 != 1).count() as u64;
+
+            assert_eq!(expected, bv.count_ones());
+            assert_eq!(len - expected, bv.count_zeros());
+
+            // Bit 0 is set, and this slice’s last block is partial.
+            if len > 0 {
+                let slice = BitSlice::new(&bv, 1 .. len);
+                assert_eq!(expected - 1, slice.count_ones());
+            }
+        }
+
+        let words: &[u16] = &[0xFFFF, 0x0101];
+        assert_eq!(18, words.count_ones());
+        assert_eq!(14, words.count_zeros());
+    }
+
+    #[test]
+    fn bit_ops() {
+        let a: BitVector<u8> = BitVector::from_ones(vec![0, 2, 3, 9, 10], 11);
+        let b: BitVector<u8> = BitVector::from_ones(vec![1, 2, 9], 11);
+
+        assert_bv!("10110000011", &a);
+        assert_bv!("01100000010", &b);
+        assert_bv!("00100000010", &a & &b);
+        assert_bv!("11110000011", &a | &b);
+        assert_bv!("11010000001", &a ^ &b);
+        assert_bv!("01001111100", !&a);
+
+        // The unused bits of the last block stay clear.
+        assert_eq!(0b001, (!a.clone()).get_block(1));
+        assert_eq!(a, !!a.clone());
+
+        let mut c = a.clone();
+        c &= &b;
+        assert_eq!(&a & &b, c);
+        c |= &a;
+        assert_eq!(a, c);
+        c ^= &a;
+        assert_eq!(BitVector::with_fill(11, false), c);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bit_ops_length_mismatch() {
+        let a: BitVector<u8> = BitVector::with_fill(11, true);
+        let b: BitVector<u8> = BitVector::with_fill(12, true);
+        let _ = &a | &b;
+    }
+
+    #[test]
+    fn hamming_distance() {
+        let a: BitVector<u8> = BitVector::from_ones(vec![0, 3, 9, 17], 20);
+        let b: BitVector<u8> = BitVector::from_ones(vec![0, 4, 9, 19], 20);
+
+        assert_eq!(0, a.hamming_distance(&a));
+        assert_eq!(4, a.hamming_distance(&b));
+        assert_eq!((&a ^ &b).count_ones(), a.hamming_distance(&b));
+
+        // Differing only in the last, partial block.
+        let c: BitVector<u8> = BitVector::from_ones(vec![0, 3, 9], 20);
+        let mut d = c.clone();
+        d.set_bit(19, true);
+        assert_eq!(1, c.hamming_distance(&d));
+
+        // Bits past the end never count, even after truncating.
+        let mut e = d.clone();
+        e.truncate(18);
+        let mut f = c.clone();
+        f.truncate(18);
+        assert_eq!(0, e.hamming_distance(&f));
+    }
+
+    #[test]
+    #[should_panic]
+    fn hamming_distance_length_mismatch() {
+        let a: BitVector<u8> = BitVector::with_fill(11, true);
+        let b: BitVector<u8> = BitVector::with_fill(12, true);
+        a.hamming_distance(&b);
+    }
+
+    #[test]
+    fn build_rank() {
+        use rank::BitRankSupport;
+        use select::Select1Support;
+
+        let mut bit_vector: BitVector<u8> = BitVector::new();
+        for i in 0 .. 100 {
+            bit_vector.push_bit(i % 7 == 2 || i % 5 == 0);
+        }
+
+        let unindexed = bit_vector.clone();
+        bit_vector.build_rank();
+        assert!(bit_vector.has_rank());
+        assert_eq!(unindexed, bit_vector);
+
+        let mut ones = 0;
+        for i in 0 .. 100 {
+            if bit_vector.get_bit(i) {
+                assert_eq!(Some(i), bit_vector.select1(ones));
+                assert_eq!(Some(i), unindexed.select1(ones));
+                ones += 1;
+            }
+
+            assert_eq!(ones, bit_vector.rank1(i));
+            assert_eq!(ones, unindexed.rank1(i));
+        }
+
+        assert_eq!(None, bit_vector.select1(ones));
+        assert_eq!(None, unindexed.select1(ones));
+
+        bit_vector.push_bit(true);
+        assert!(!bit_vector.has_rank());
+        assert_eq!(ones + 1, bit_vector.rank1(100));
+        assert_eq!(Some(100), bit_vector.select1(ones));
+
+        bit_vector.build_rank();
+        bit_vector.set_bit(0, false);
+        assert!(!bit_vector.has_rank());
+        assert_eq!(ones, bit_vector.rank1(100));
+    }
+
+    #[test]
+    fn build_rank_superblocks() {
+        use rank::BitRankSupport;
+        use select::Select1Support;
+        use storage::BlockType;
+
+        fn check<Block: BlockType>() {
+            // Several superblocks, with a run of zeros spanning two so
+            // that some share a count.
+            let mut bits: BitVector<Block> = BitVector::new();
+            for i in 0 .. 3000u64 {
+                bits.push_bit(i % 3 == 0 && !(700 .. 1900).contains(&i));
+            }
+
+            let unindexed = bits.clone();
+            bits.build_rank();
+
+            let mut ones = 0;
+            for i in 0 .. bits.bit_len() {
+                if bits.get_bit(i) {
+                    assert_eq!(Some(i), bits.select1(ones));
+                    ones += 1;
+                }
+                assert_eq!(unindexed.rank1(i), bits.rank1(i));
+            }
+            assert_eq!(None, bits.select1(ones));
+        }
+
+        check::<u8>();
+        check::<u32>();
+        check::<u64>();
+        check::<u128>();
+    }
+
+    #[test]
+    fn build_rank_space() {
+        use space_usage::SpaceUsage;
+
+        let mut bits: BitVector<u8> = BitVector::with_fill(512 * 100, true);
+        let before = bits.heap_bytes();
+        bits.build_rank();
+        assert_eq!(before + 100 * 8, bits.heap_bytes());
+    }
+
+    #[test]
+    fn keep_first_ones() {
+        let mut bit_vector: BitVector<u8> = BitVector::new();
+        for i in 0 .. 30 {
+            bit_vector.push_bit(i % 3 != 1);
+        }
+        let ones: Vec<u64> = (0 .. 30).filter(|i| i % 3 != 1).collect();
+
+        for n in 0 .. 25 {
+            let mut capped = bit_vector.clone();
+            capped.keep_first_ones(n);
+
+            let kept: Vec<u64> = (0 .. capped.bit_len())
+                .filter(|&i| capped.get_bit(i))
+                .collect();
+
+            assert_eq!(30, capped.bit_len());
+            assert_eq!(&ones[.. n.min(20) as usize], &kept[..]);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use serde_json;
+
+        let mut bv: BitVector<u16> =
+            BitVector::from_ones(vec![1, 5, 16, 19], 20);
+        bv.build_rank();
+
+        let json = serde_json::to_string(&bv).unwrap();
+        let copy: BitVector<u16> = serde_json::from_str(&json).unwrap();
+        assert_eq!(bv, copy);
+        assert!(!copy.has_rank());
+
+        // Too many blocks for the length, and a set bit past the end.
+        assert!(serde_json::from_str::<BitVector<u16>>(
+            r#"{"base":{"len":20,"vec":[1,2,3]}}"#).is_err());
+        assert!(serde_json::from_str::<BitVector<u16>>(
+            r#"{"base":{"len":20,"vec":[1,16]}}"#).is_err());
+    }
+
+    #[test]
+    fn slice() {
+        use quickcheck::quickcheck;
+
+        use std::cmp;
+        use storage::BlockType;
+
+        fn prop(bits: Vec<bool>, start: u8, len: u8) -> bool {
+            let mut bv: BitVector<u8> = BitVector::new();
+            for bit in bits {
+                bv.push_bit(bit);
+            }
+
+            let start = start as u64 % (bv.bit_len() + 1);
+            let len = len as u64 % (bv.bit_len() - start + 1);
+            let slice = bv.slice(start, len);
+
+            slice.bit_len() == len
+                && (0 .. len).all(|i| slice.get_bit(i)
+                                      == bv.get_bit(start + i))
+                && (0 .. slice.block_len()).all(|i| {
+                    let position = u8::mul_nbits(i);
+                    let count = cmp::min(8, len - position) as usize;
+                    slice.get_block(i)
+                        == bv.get_bits(start + position, count)
+                })
+        }
+
+        quickcheck(prop as fn(Vec<bool>, u8, u8) -> bool);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_oob() {
+        let bv: BitVector<u8> = BitVector::with_fill(10, true);
+        bv.slice(4, 7);
+    }
+
+    #[test]
+    fn append() {
+        fn check(a: &BitVector<u8>, b: &BitVector<u8>) {
+            let mut result = a.clone();
+            result.append(b);
+
+            assert_eq!(a.bit_len() + b.bit_len(), result.bit_len());
+            assert_eq!(format!("{:b}{:b}", a, b), format!("{:b}", result));
+        }
+
+        let mut a: BitVector<u8> = BitVector::new();
+        let mut b: BitVector<u8> = BitVector::new();
+        for i in 0 .. 27 {
+            b.push_bit(i % 3 == 0 || i % 5 == 0);
+        }
+
+        // Every alignment of `a`, with `b` both long and short.
+        for i in 0 .. 20 {
+            let mut prefix = b.clone();
+            prefix.truncate(i);
+
+            check(&a, &b);
+            check(&a, &prefix);
+            a.push_bit(i % 2 == 0);
+        }
+
+        check(&BitVector::new(), &BitVector::new());
+    }
+
+    #[test]
+    fn reverse() {
+        let mut bv: BitVector<u8> =
+            BitVector::from_ones(vec![0, 1, 5, 9, 12], 13);
+        bv.reverse();
+        assert_bv!("1001000100011", bv);
+
+        for len in 0 .. 40 {
+            let original: BitVector<u8> =
+                BitVector::from_ones((0 .. len).filter(|i| i % 3 != 1), len);
+            let mut bv = original.clone();
+
+            bv.reverse();
+            assert_eq!(len, bv.bit_len());
+            for i in 0 .. len {
+                assert_eq!(original.get_bit(i), bv.get_bit(len - 1 - i));
+            }
+
+            bv.reverse();
+            assert_eq!(original, bv);
+        }
+    }
+
+    #[test]
+    fn insert_remove_bit() {
+        use quickcheck::quickcheck;
+
+        // Each edit is an insertion if `value` is `Some` and a removal
+        // otherwise; positions are taken modulo the length.
+        fn prop(edits: Vec<(u16, Option<bool>)>) -> bool {
+            let mut expected: Vec<bool> = Vec::new();
+            let mut actual: BitVector<u8> = BitVector::new();
+
+            for (position, value) in edits {
+                match value {
+                    Some(value) => {
+                        let index = position as usize % (expected.len() + 1);
+                        expected.insert(index, value);
+                        actual.insert_bit(index as u64, value);
+                    }
+                    None if !expected.is_empty() => {
+                        let index = position as usize % expected.len();
+                        if expected.remove(index)
+                            != actual.remove_bit(index as u64) {
+                            return false;
+                        }
+                    }
+                    None => {}
+                }
+
+                if actual.bit_len() != expected.len() as u64
+                    || actual.iter().ne(expected.iter().cloned()) {
+                    return false;
+                }
+            }
+
+            // The bits past the end stay clear.
+            let mut copy = BitVector::new();
+            for &bit in &expected {
+                copy.push_bit(bit);
+            }
+            copy == actual
+        }
+
+        quickcheck(prop as fn(Vec<(u16, Option<bool>)>) -> bool);
+    }
+
+    #[test]
+    fn insert_remove_bit_example() {
+        let mut bv: BitVector<u8> = BitVector::from_ones(vec![0, 7, 8], 10);
+        bv.insert_bit(7, true);
+        assert_bv!("10000001110", bv);
+        bv.insert_bit(11, true);
+        assert_bv!("100000011101", bv);
+        assert!(bv.remove_bit(0));
+        assert_bv!("00000011101", bv);
+        assert!(!bv.remove_bit(9));
+        assert_bv!("0000001111", bv);
+    }
+
+    #[test]
+    fn shift_bits() {
+        use quickcheck::quickcheck;
+
+        fn from_bools(bits: &[bool]) -> BitVector<u8> {
+            let mut result = BitVector::new();
+            for &bit in bits {
+                result.push_bit(bit);
+            }
+            result
+        }
+
+        fn prop(bits: Vec<bool>, n: u8) -> bool {
+            let n = n as usize % (bits.len() + 10);
+            let len = bits.len();
+
+            let shl: Vec<bool> = (0 .. len)
+                .map(|i| i >= n && bits[i - n])
+                .collect();
+            let shr: Vec<bool> = (0 .. len)
+                .map(|i| i + n < len && bits[i + n])
+                .collect();
+
+            let bv = from_bools(&bits);
+            bv.shl_bits(n as u64) == from_bools(&shl)
+                && bv.shr_bits(n as u64) == from_bools(&shr)
+        }
+
+        quickcheck(prop as fn(Vec<bool>, u8) -> bool);
+    }
+
+    #[test]
+    fn shift_bits_example() {
+        let bv: BitVector<u8> = BitVector::from_ones(vec![0, 7, 8, 11], 12);
+        assert_bv!("100000011001", bv);
+        assert_bv!("000100000011", bv.shl_bits(3));
+        assert_bv!("000011001000", bv.shr_bits(3));
+        assert_bv!("000000000001", bv.shl_bits(11));
+        assert_bv!("100000000000", bv.shr_bits(11));
+        assert_bv!("000000000000", bv.shl_bits(12));
+        assert_bv!("000000000000", bv.shr_bits(100));
+        assert_eq!(bv, bv.shl_bits(0));
+    }
 }
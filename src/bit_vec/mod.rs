@@ -9,5 +9,8 @@ pub use self::bit_vector::*;
 mod bit_slice;
 pub use self::bit_slice::*;
 
+mod positions;
+pub use self::positions::*;
+
 mod prim;
 pub use self::prim::*;
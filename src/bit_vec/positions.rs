@@ -0,0 +1,107 @@
+use num_traits::{One, PrimInt, Zero};
+
+use bit_vec::traits::*;
+use storage::BlockType;
+
+/// Iterator over the positions of the 1s (or 0s) in a bit vector.
+///
+/// Created by [`BitVec::iter_ones`](trait.BitVec.html#method.iter_ones)
+/// and [`BitVec::iter_zeros`](trait.BitVec.html#method.iter_zeros).
+pub struct Positions<'a, Base: 'a + BitVec + ?Sized> {
+    data: &'a Base,
+    value: bool,
+    block_index: usize,
+    // The unvisited bits of the current block that match `value`, as 1s.
+    current: Base::Block,
+}
+
+impl<'a, Base: 'a + BitVec + ?Sized> Positions<'a, Base> {
+    /// Creates an iterator over the positions of the bits equal to
+    /// `value`.
+    pub fn new(data: &'a Base, value: bool) -> Self {
+        let mut result = Positions {
+            data,
+            value,
+            block_index: 0,
+            current: Base::Block::zero(),
+        };
+
+        if data.block_len() > 0 {
+            result.current = result.load_block(0);
+        }
+
+        result
+    }
+
+    fn load_block(&self, index: usize) -> Base::Block {
+        let block = self.data.get_block(index);
+        let block = if self.value {block} else {!block};
+
+        if index + 1 == self.data.block_len() {
+            let last_bits = Base::Block::last_block_bits(self.data.bit_len());
+            block & Base::Block::low_mask(last_bits)
+        } else {
+            block
+        }
+    }
+}
+
+impl<'a, Base: 'a + BitVec + ?Sized> Iterator for Positions<'a, Base> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while self.current == Base::Block::zero() {
+            if self.block_index + 1 >= self.data.block_len() {
+                return None;
+            }
+
+            self.block_index += 1;
+            self.current = self.load_block(self.block_index);
+        }
+
+        let offset = self.current.trailing_zeros() as u64;
+        self.current = self.current & (self.current - Base::Block::one());
+        Some(Base::Block::mul_nbits(self.block_index) + offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::quickcheck;
+
+    use bit_vec::*;
+
+    fn naive<BV: BitVec + ?Sized>(bits: &BV, value: bool) -> Vec<u64> {
+        (0 .. bits.bit_len()).filter(|&i| bits.get_bit(i) == value).collect()
+    }
+
+    #[test]
+    fn slice() {
+        let words: &[u64] = &[0b1011, 0, !0, 1 << 63];
+        assert_eq!(naive(words, true), words.iter_ones().collect::<Vec<_>>());
+        assert_eq!(naive(words, false), words.iter_zeros().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn empty() {
+        let bv: BitVector = BitVector::new();
+        assert_eq!(None, bv.iter_ones().next());
+        assert_eq!(None, bv.iter_zeros().next());
+    }
+
+    fn bit_vector_prop(bits: Vec<bool>) -> bool {
+        let mut bv: BitVector<u8> = BitVector::new();
+        for &bit in &bits {
+            bv.push_bit(bit);
+        }
+
+        naive(&bv, true) == bv.iter_ones().collect::<Vec<_>>()
+            && naive(&bv, false) == bv.iter_zeros().collect::<Vec<_>>()
+            && naive(&bits, true) == bits.iter_ones().collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn bit_vector_qc() {
+        quickcheck(bit_vector_prop as fn(Vec<bool>) -> bool);
+    }
+}
@@ -67,4 +67,5 @@ impl_bits_prim!(u8);
 impl_bits_prim!(u16);
 impl_bits_prim!(u32);
 impl_bits_prim!(u64);
+impl_bits_prim!(u128);
 impl_bits_prim!(usize);
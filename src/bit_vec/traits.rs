@@ -1,5 +1,8 @@
-use num_traits::{One, Zero, ToPrimitive};
+use std::ops::Range;
 
+use num_traits::{One, PrimInt, Zero, ToPrimitive};
+
+use bit_vec::Positions;
 use storage::{Address, BlockType};
 
 /// Read-only bit vector operations.
@@ -96,6 +99,122 @@ pub trait BitVec {
 
         (high_bits << margin) | low_bits
     }
+
+    /// Gets the bits in `range`, interpreted as a little-endian integer.
+    ///
+    /// This is the same as `get_bits(range.start, range.end - range.start)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is inverted, goes out of bounds, or is wider
+    /// than a block.
+    fn get_range(&self, range: Range<u64>) -> Self::Block {
+        assert!(range.start <= range.end, "BitVec::get_range: inverted range");
+        assert!(range.end <= self.bit_len(), "BitVec::get_range: out of bounds");
+
+        let count = range.end - range.start;
+        assert!(count <= Self::Block::nbits() as u64,
+                "BitVec::get_range: range too wide");
+
+        self.get_bits(range.start, count as usize)
+    }
+
+    /// Counts the 1 bits.
+    ///
+    /// The default implementation counts a block at a time, masking off
+    /// any bits of the last block past `bit_len()`.
+    fn count_ones(&self) -> u64 {
+        let len = self.bit_len();
+        let full_blocks = Self::Block::div_nbits(len);
+
+        let mut result = (0 .. full_blocks)
+            .map(|i| self.get_block(i).popcount() as u64)
+            .sum();
+
+        let extra = Self::Block::mod_nbits(len);
+        if extra > 0 {
+            let last = self.get_block(full_blocks)
+                & Self::Block::low_mask(extra);
+            result += last.popcount() as u64;
+        }
+
+        result
+    }
+
+    /// Counts the 0 bits.
+    fn count_zeros(&self) -> u64 {
+        self.bit_len() - self.count_ones()
+    }
+
+    /// Returns an iterator over the positions of the 1 bits, in
+    /// increasing order.
+    ///
+    /// The default implementation scans a block at a time, so it
+    /// doesn’t need a select structure.
+    fn iter_ones(&self) -> Positions<'_, Self> {
+        Positions::new(self, true)
+    }
+
+    /// Returns an iterator over the positions of the 0 bits, in
+    /// increasing order.
+    fn iter_zeros(&self) -> Positions<'_, Self> {
+        Positions::new(self, false)
+    }
+
+    /// Finds the position of the first 1 bit at or after `from`.
+    ///
+    /// Returns `None` if there is no such bit, including when `from` is
+    /// out of bounds.
+    fn next_set_bit(&self, from: u64) -> Option<u64> {
+        let len = self.bit_len();
+        if from >= len { return None; }
+
+        let address = Address::new::<Self::Block>(from);
+        let last_block = self.block_len() - 1;
+        let mut block = self.get_block(address.block_index)
+            & !Self::Block::low_mask(address.bit_offset);
+
+        for index in address.block_index .. last_block + 1 {
+            if index > address.block_index {
+                block = self.get_block(index);
+            }
+            if index == last_block {
+                block = block & Self::Block::low_mask(
+                    Self::Block::last_block_bits(len));
+            }
+            if block != Self::Block::zero() {
+                return Some(Self::Block::mul_nbits(index)
+                            + block.trailing_zeros() as u64);
+            }
+        }
+
+        None
+    }
+
+    /// Finds the position of the last 1 bit at or before `from`.
+    ///
+    /// Returns `None` if there is no such bit, including when `from` is
+    /// out of bounds.
+    fn prev_set_bit(&self, from: u64) -> Option<u64> {
+        if from >= self.bit_len() { return None; }
+
+        let address = Address::new::<Self::Block>(from);
+        let mut block = self.get_block(address.block_index)
+            & Self::Block::low_mask(address.bit_offset + 1);
+
+        for index in (0 .. address.block_index + 1).rev() {
+            if index < address.block_index {
+                block = self.get_block(index);
+            }
+            if block != Self::Block::zero() {
+                let offset = Self::Block::nbits() - 1
+                    - block.leading_zeros() as usize;
+                return Some(Self::Block::mul_nbits(index) + offset as u64);
+            }
+        }
+
+        None
+    }
 }
 
 /// Mutable bit vector operations that don’t affect the length.
@@ -184,6 +303,29 @@ pub trait BitVecMut: BitVec {
         self.set_block(address.block_index, new_block1);
         self.set_block(address.block_index + 1, new_block2);
     }
+
+    /// Sets the bits in `range` to `value`, interpreted as a little-endian
+    /// integer.
+    ///
+    /// This is the same as
+    /// `set_bits(range.start, range.end - range.start, value)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is inverted, goes out of bounds, or is wider
+    /// than a block.
+    fn set_range_bits(&mut self, range: Range<u64>, value: Self::Block) {
+        assert!(range.start <= range.end,
+                "BitVecMut::set_range_bits: inverted range");
+        assert!(range.end <= self.bit_len(),
+                "BitVecMut::set_range_bits: out of bounds");
+
+        let count = range.end - range.start;
+        assert!(count <= Self::Block::nbits() as u64,
+                "BitVecMut::set_range_bits: range too wide");
+
+        self.set_bits(range.start, count as usize, value);
+    }
 }
 
 /// Bit vector operations that change the length.
@@ -245,48 +387,90 @@ impl<Block: BlockType> BitVecMut for [Block] {
     }
 }
 
-impl<'a, Block: BlockType> BitVec for &'a [Block] {
-    type Block = Block;
+// References forward to their referents, so a structure that owns its
+// `Store` can also be built over a borrowed bit vector, such as
+// `Rank9::new(&int_vector)`.
+
+impl<T: BitVec + ?Sized> BitVec for &T {
+    type Block = T::Block;
 
     #[inline]
     fn bit_len(&self) -> u64 {
-        self.len() as u64 * Block::nbits() as u64
+        (**self).bit_len()
     }
 
     #[inline]
     fn block_len(&self) -> usize {
-        self.len()
+        (**self).block_len()
     }
 
     #[inline]
-    fn get_block(&self, position: usize) -> Block {
-        self[position]
+    fn get_bit(&self, position: u64) -> bool {
+        (**self).get_bit(position)
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> T::Block {
+        (**self).get_block(position)
+    }
+
+    #[inline]
+    fn get_bits(&self, start: u64, count: usize) -> T::Block {
+        (**self).get_bits(start, count)
+    }
+
+    fn count_ones(&self) -> u64 {
+        (**self).count_ones()
     }
 }
 
-impl<'a, Block: BlockType> BitVec for &'a mut [Block] {
-    type Block = Block;
+impl<T: BitVec + ?Sized> BitVec for &mut T {
+    type Block = T::Block;
 
     #[inline]
     fn bit_len(&self) -> u64 {
-        self.len() as u64 * Block::nbits() as u64
+        (**self).bit_len()
     }
 
     #[inline]
     fn block_len(&self) -> usize {
-        self.len()
+        (**self).block_len()
     }
 
     #[inline]
-    fn get_block(&self, position: usize) -> Block {
-        self[position]
+    fn get_bit(&self, position: u64) -> bool {
+        (**self).get_bit(position)
+    }
+
+    #[inline]
+    fn get_block(&self, position: usize) -> T::Block {
+        (**self).get_block(position)
+    }
+
+    #[inline]
+    fn get_bits(&self, start: u64, count: usize) -> T::Block {
+        (**self).get_bits(start, count)
+    }
+
+    fn count_ones(&self) -> u64 {
+        (**self).count_ones()
     }
 }
 
-impl<'a, Block: BlockType> BitVecMut for &'a mut [Block] {
+impl<T: BitVecMut + ?Sized> BitVecMut for &mut T {
     #[inline]
-    fn set_block(&mut self, position: usize, value: Block) {
-        self[position] = value;
+    fn set_bit(&mut self, position: u64, value: bool) {
+        (**self).set_bit(position, value)
+    }
+
+    #[inline]
+    fn set_block(&mut self, position: usize, value: T::Block) {
+        (**self).set_block(position, value)
+    }
+
+    #[inline]
+    fn set_bits(&mut self, start: u64, count: usize, value: T::Block) {
+        (**self).set_bits(start, count, value)
     }
 }
 
@@ -347,3 +531,46 @@ impl BitVecPush for Vec<bool> {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use quickcheck::quickcheck;
+
+    use super::*;
+    use bit_vec::BitSlice;
+
+    fn next_prev_prop(words: Vec<u16>, len: u16, from: u16) -> bool {
+        let len = len as u64 % (words.bit_len() + 1);
+        let bits = BitSlice::new(&*words, .. len);
+        let from = from as u64 % (bits.bit_len() + 2);
+
+        let next = (from .. bits.bit_len()).find(|&i| bits.get_bit(i));
+        let prev = if from < bits.bit_len() {
+            (0 .. from + 1).rev().find(|&i| bits.get_bit(i))
+        } else {
+            None
+        };
+
+        bits.next_set_bit(from) == next && bits.prev_set_bit(from) == prev
+    }
+
+    #[test]
+    fn next_prev_set_bit_qc() {
+        quickcheck(next_prev_prop as fn(Vec<u16>, u16, u16) -> bool);
+    }
+
+    #[test]
+    fn next_prev_set_bit() {
+        let words: &[u8] = &[0b0000_0100, 0, 0b1000_0001];
+
+        assert_eq!(Some(2), words.next_set_bit(0));
+        assert_eq!(Some(16), words.next_set_bit(3));
+        assert_eq!(Some(23), words.next_set_bit(17));
+        assert_eq!(None, words.next_set_bit(24));
+
+        assert_eq!(Some(23), words.prev_set_bit(23));
+        assert_eq!(Some(16), words.prev_set_bit(22));
+        assert_eq!(Some(2), words.prev_set_bit(15));
+        assert_eq!(None, words.prev_set_bit(1));
+        assert_eq!(None, words.prev_set_bit(24));
+    }
+}
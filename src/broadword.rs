@@ -21,7 +21,7 @@
 //!     In the paper, the shifted `s` appears as `x`.
 
 use rank::{BitRankSupport, RankSupport};
-use select::Select1Support;
+use select::{Select0Support, Select1Support, SelectSupport};
 use storage::BlockType;
 
 /// Newtype for treating a `u64` as a rank or select structure.
@@ -50,6 +50,20 @@ impl Select1Support for Broadword {
     }
 }
 
+impl Select0Support for Broadword {
+    fn select0(&self, index: u64) -> Option<u64> {
+        select1(index as usize, !self.0).map(|u| u as u64)
+    }
+}
+
+impl SelectSupport for Broadword {
+    type Over = bool;
+
+    fn select(&self, index: u64, value: bool) -> Option<u64> {
+        if value {self.select1(index)} else {self.select0(index)}
+    }
+}
+
 /// Has the lowest bit of every byte set: `0x0101_0101_0101_0101`.
 pub const L8: u64 = 0x0101_0101_0101_0101;
 
@@ -66,6 +80,24 @@ pub fn count_ones(mut x: u64) -> usize {
     (x.wrapping_mul(L8) >> 56) as usize
 }
 
+/// Counts the number of ones in a `u64`, portably.
+///
+/// On targets with a native population count instruction this is
+/// `x.count_ones()`; elsewhere it is the broadword
+/// [`count_ones`](fn.count_ones.html). The choice is made at compile
+/// time, never by runtime feature detection, so the code path is fixed
+/// by the target.
+#[inline]
+pub fn popcount(x: u64) -> usize {
+    if cfg!(any(target_feature = "popcnt",
+                target_arch = "aarch64",
+                target_arch = "wasm32")) {
+        x.count_ones() as usize
+    } else {
+        count_ones(x)
+    }
+}
+
 /// Finds the index of the `r`th one bit in `x`.
 ///
 /// Uses the broadword algorithm from Vigna.
@@ -112,7 +144,9 @@ mod test {
     use quickcheck::{quickcheck, TestResult};
 
     use super::*;
-    use select::{BinSearchSelect, Select1Support};
+    use rank::{BitRankSupport, RankSupport};
+    use select::{BinSearchSelect, Select0Support, Select1Support,
+                 SelectSupport};
 
     #[test]
     fn count_ones_0() {
@@ -216,6 +250,36 @@ mod test {
         quickcheck(select1_prop_hash as fn(u8, u64) -> TestResult);
     }
 
+    fn rank0_select0_prop(r: u8, x: u64) -> TestResult {
+        if r >= 64 { return TestResult::discard(); }
+
+        let word = Broadword(x);
+        let naive_rank0 = (0 .. r as u64 + 1)
+            .filter(|&i| !x.get_bit(i as usize))
+            .count() as u64;
+        let naive_select0 = (0 .. 64)
+            .filter(|&i| !x.get_bit(i as usize))
+            .nth(r as usize);
+
+        TestResult::from_bool(
+            word.rank0(r as u64) == naive_rank0
+                && word.rank(r as u64, false) == naive_rank0
+                && word.select0(r as u64) == naive_select0
+                && word.select(r as u64, false) == naive_select0
+                && word.select(r as u64, true) == word.select1(r as u64))
+    }
+
+    #[test]
+    fn rank0_select0_qc() {
+        quickcheck(rank0_select0_prop as fn(u8, u64) -> TestResult);
+    }
+
+    #[test]
+    fn select0_all_ones() {
+        assert_eq!(None, Broadword(!0).select0(0));
+        assert_eq!(Some(63), Broadword(!0 >> 1).select0(0));
+    }
+
     fn u_nz8_prop((n0, n1, n2, n3): (u64, u64, u64, u64)) -> bool {
         let n = hash(&(n0, n1, n2, n3));
         let r = u_nz8(n);
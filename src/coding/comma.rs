@@ -1,16 +1,47 @@
 use super::*;
 use internal::errors::*;
 use stream::*;
+use storage::BlockType;
+
+/// A base-*k* comma code.
+///
+/// Encodes the base-*k* digits of the value, most significant first,
+/// followed by the digit *k* as a terminating “comma.” Each digit takes
+/// ⌈lg(*k* + 1)⌉ bits, so bases one less than a power of two waste no
+/// codes.
+#[derive(Clone, Copy, Debug)]
+pub struct Comma {
+    base: u32,
+}
+
+/// `Comma::new(3)` encodes in base 3, using 2 bits per digit.
+pub const COMMA: Comma = Comma { base: 3 };
+
+impl Comma {
+    /// Creates a comma code in the given base.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base < 2`.
+    pub fn new(base: u32) -> Self {
+        assert!(base >= 2, "Comma::new: base must be at least 2");
+        Comma { base }
+    }
 
-/// `Comma(n)` encodes in base 2<sup>n</sup> - 1, using n bits per digit.
-pub struct Comma(pub u8);
+    /// The base of the code.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
 
-/// `Comma(2)` encodes in base 3.
-pub const COMMA: Comma = Comma(2);
+    fn digit_bits(&self) -> usize {
+        (self.base as u64 + 1).ceil_lg()
+    }
+}
 
 impl UniversalCode for Comma {
     fn encode<W: BitWrite>(&self, sink: &mut W, mut value: u64) -> Result<()> {
-        let base = (1 << self.0) - 1;
+        let base = self.base as u64;
+        let bits = self.digit_bits();
         let mut stack: Vec<u64> = Vec::new();
 
         while value > 0 {
@@ -19,25 +50,31 @@ impl UniversalCode for Comma {
         }
 
         while let Some(digit) = stack.pop() {
-            try!(sink.write_int(self.0 as usize, digit));
+            try!(sink.write_int(bits, digit));
         }
 
-        try!(sink.write_int(self.0 as usize, base));
+        try!(sink.write_int(bits, base));
 
         Ok(())
     }
 
     fn decode<R: BitRead>(&self, source: &mut R) -> Result<Option<u64>> {
-        let base = (1 << self.0) - 1;
-        let mut result = 0;
+        let base = self.base as u64;
+        let bits = self.digit_bits();
+        let mut result: u64 = 0;
         let mut consumed = false;
 
         loop {
-            if let Some(digit) = try!(source.read_int::<u64>(self.0 as usize)) {
+            if let Some(digit) = try!(source.read_int::<u64>(bits)) {
                 if digit == base { return Ok(Some(result)) }
+                if digit > base { return invalid_code("Comma::decode") }
 
                 consumed = true;
-                result = result * base + digit;
+                result = match result.checked_mul(base)
+                                     .and_then(|r| r.checked_add(digit)) {
+                    Some(r) => r,
+                    None => return too_many_bits("Comma::decode"),
+                };
             } else if consumed {
                 return out_of_bits("Comma::decode");
             } else {
@@ -53,6 +90,7 @@ mod test {
     use quickcheck::quickcheck;
     use coding::*;
     use coding::properties;
+    use stream::BitWrite;
 
     #[test]
     fn enc234() {
@@ -69,29 +107,70 @@ mod test {
     }
 
     #[test]
-    fn qc_comma2() {
+    fn qc_base2() {
+        fn prop(v: Vec<u64>) -> bool {
+            properties::code_decode(&Comma::new(2), v)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn qc_base3() {
+        fn prop(v: Vec<u64>) -> bool {
+            properties::code_decode(&Comma::new(3), v)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn qc_base7() {
         fn prop(v: Vec<u64>) -> bool {
-            properties::code_decode(&Comma(2), v)
+            properties::code_decode(&Comma::new(7), v)
         }
 
         quickcheck(prop as fn(Vec<u64>) -> bool);
     }
 
     #[test]
-    fn qc_comma3() {
+    fn qc_base10() {
         fn prop(v: Vec<u64>) -> bool {
-            properties::code_decode(&Comma(3), v)
+            properties::code_decode(&Comma::new(10), v)
         }
 
         quickcheck(prop as fn(Vec<u64>) -> bool);
     }
 
     #[test]
-    fn qc_comma4() {
+    fn qc_base15() {
         fn prop(v: Vec<u64>) -> bool {
-            properties::code_decode(&Comma(4), v)
+            properties::code_decode(&Comma::new(15), v)
         }
 
         quickcheck(prop as fn(Vec<u64>) -> bool);
     }
+
+    #[test]
+    fn digit_bits() {
+        assert_eq!(2, COMMA.digit_bits());
+        assert_eq!(2, Comma::new(2).digit_bits());
+        assert_eq!(4, Comma::new(10).digit_bits());
+        assert_eq!(4, Comma::new(15).digit_bits());
+        assert_eq!(5, Comma::new(16).digit_bits());
+    }
+
+    #[test]
+    fn invalid_digit() {
+        let mut dv = VecDeque::<bool>::new();
+        // In base 10 the comma is 1010, so 1111 is not a digit.
+        dv.write_int(4, 0b1111u64).unwrap();
+        assert!(Comma::new(10).decode(&mut dv).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn base_too_small() {
+        Comma::new(1);
+    }
 }
@@ -1,4 +1,5 @@
 use super::*;
+use internal::counting::CountingReader;
 use internal::errors::*;
 use stream::*;
 
@@ -8,6 +9,7 @@ use stream::*;
 ///
 /// An Elias code first encodes the size of the number using some other
 /// code—this is the `Header` parameter.
+#[derive(Clone, Copy, Debug)]
 pub struct Elias<Header: UniversalCode>(pub Header);
 
 /// An Elias gamma code encodes the header in unary.
@@ -23,6 +25,7 @@ pub type Delta = Elias<Lift0<Gamma>>;
 pub const DELTA : Delta = Elias(Lift0(GAMMA));
 
 /// An Elias omega code iterates the Elias encoding.
+#[derive(Clone, Copy, Debug)]
 pub struct Omega;
 
 const WORD_BITS: u32 = 64;
@@ -94,6 +97,37 @@ impl UniversalCode for Omega {
     }
 }
 
+impl<Header: UniversalCode> Elias<Header> {
+    /// Reads a value from `source`, along with the number of bits it
+    /// occupied.
+    ///
+    /// `Ok(None)` indicates (benign) EOF.
+    pub fn decode_with_len<R: BitRead>(&self, source: &mut R)
+                                       -> Result<Option<(u64, usize)>> {
+        decode_counting(self, source)
+    }
+}
+
+impl Omega {
+    /// Reads a value from `source`, along with the number of bits it
+    /// occupied.
+    ///
+    /// `Ok(None)` indicates (benign) EOF.
+    pub fn decode_with_len<R: BitRead>(&self, source: &mut R)
+                                       -> Result<Option<(u64, usize)>> {
+        decode_counting(self, source)
+    }
+}
+
+fn decode_counting<C, R>(code: &C, source: &mut R)
+                         -> Result<Option<(u64, usize)>>
+    where C: UniversalCode, R: BitRead
+{
+    let mut counter = CountingReader::new(source);
+    let result = try!(code.decode(&mut counter));
+    Ok(result.map(|value| (value, counter.count)))
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::VecDeque;
@@ -173,4 +207,58 @@ mod test {
 
         quickcheck(prop_omega as fn(Vec<u64>) -> bool);
     }
+
+    fn prop_decode_with_len<F>(encode: F,
+                               decode: fn(&mut VecDeque<bool>)
+                                          -> Option<(u64, usize)>,
+                               values: Vec<u64>) -> bool
+        where F: Fn(&mut VecDeque<bool>, u64)
+    {
+        let values: Vec<u64> = values.into_iter().map(|v| v + 1).collect();
+
+        let mut dv = VecDeque::<bool>::new();
+        for &value in &values {
+            encode(&mut dv, value);
+        }
+
+        values.iter().all(|&value| {
+            let mut alone = VecDeque::<bool>::new();
+            encode(&mut alone, value);
+            decode(&mut dv) == Some((value, alone.len()))
+        }) && decode(&mut dv).is_none()
+    }
+
+    #[test]
+    fn qc_decode_with_len() {
+        fn prop(v: Vec<u64>) -> bool {
+            let v: Vec<u64> = v.into_iter().map(|x| x >> 1).collect();
+
+            prop_decode_with_len(|dv, x| GAMMA.encode(dv, x).unwrap(),
+                                 |dv| GAMMA.decode_with_len(dv).unwrap(),
+                                 v.clone())
+            && prop_decode_with_len(|dv, x| DELTA.encode(dv, x).unwrap(),
+                                    |dv| DELTA.decode_with_len(dv).unwrap(),
+                                    v.clone())
+            && prop_decode_with_len(|dv, x| Omega.encode(dv, x).unwrap(),
+                                    |dv| Omega.decode_with_len(dv).unwrap(),
+                                    v)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn decode_with_len() {
+        let mut dv = VecDeque::<bool>::new();
+        GAMMA.encode(&mut dv, 1).unwrap();
+        GAMMA.encode(&mut dv, 5).unwrap();
+        Omega.encode(&mut dv, 1).unwrap();
+        Omega.encode(&mut dv, 17).unwrap();
+
+        assert_eq!(Some((1, 1)), GAMMA.decode_with_len(&mut dv).unwrap());
+        assert_eq!(Some((5, 5)), GAMMA.decode_with_len(&mut dv).unwrap());
+        assert_eq!(Some((1, 1)), Omega.decode_with_len(&mut dv).unwrap());
+        assert_eq!(Some((17, 11)), Omega.decode_with_len(&mut dv).unwrap());
+        assert_eq!(None, Omega.decode_with_len(&mut dv).unwrap());
+    }
 }
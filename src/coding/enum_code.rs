@@ -0,0 +1,234 @@
+//! Enumerative coding of a single block of up to 64 bits.
+//!
+//! A block of `block_size` bits with `class` 1s is one of
+//! `binomial(block_size, class)` possibilities, so given its class it
+//! can be stored as an *offset*, its index among them, in
+//! `offset_bits(block_size, class)` bits. This is the representation
+//! used by [`RrrVec`](../../rank/struct.RrrVec.html).
+//!
+//! The blocks of a class are numbered in lexicographic order, scanning
+//! from the high bit down. Besides encoding and decoding, rank and
+//! select can be computed from the offset directly, stopping as soon as
+//! the answer is known.
+//!
+//! ```
+//! use succinct::coding::enum_code;
+//!
+//! let block = 0b1011_0010u64;
+//! let class = block.count_ones() as usize;
+//! let offset = enum_code::encode(8, class, block);
+//!
+//! assert!(offset < enum_code::binomial(8, class));
+//! assert_eq!(block, enum_code::decode(8, class, offset));
+//!
+//! // Ones at or below bit 4:
+//! assert_eq!(2, enum_code::rank(8, class, offset, 4));
+//! assert_eq!(Some(5), enum_code::select1(8, class, offset, 2));
+//! assert_eq!(Some(2), enum_code::select0(8, class, offset, 1));
+//! ```
+
+use storage::BlockType;
+
+/// Computes `n choose k`.
+///
+/// # Panics
+///
+/// Debug mode only: panics if `n > 64` or `k > n`.
+pub fn binomial(n: usize, k: usize) -> u64 {
+    debug_assert!(n <= 64 && k <= n, "enum_code::binomial: out of range");
+
+    // Each partial product is itself a binomial coefficient, so the
+    // division is exact.
+    (1 .. k as u128 + 1).fold(1, |acc, j| {
+        acc * (n as u128 - k as u128 + j) / j
+    }) as u64
+}
+
+/// The number of bits needed for the offset of a block of the given
+/// size and class.
+pub fn offset_bits(block_size: usize, class: usize) -> usize {
+    binomial(block_size, class).ceil_lg()
+}
+
+/// Encodes the low `block_size` bits of `block`, which must contain
+/// exactly `class` 1s, as an offset less than
+/// `binomial(block_size, class)`.
+pub fn encode(block_size: usize, class: usize, block: u64) -> u64 {
+    debug_assert!(block_size <= 64, "enum_code::encode: block too large");
+
+    // At each position, the blocks with a 0 there come before those
+    // with a 1, and there are `i choose k` of the former, where `i` is
+    // the number of positions below and `k` is the number of 1s still
+    // to place. We track `total`, the number of blocks with the current
+    // prefix, and derive `i choose k` from it.
+    let mut total = binomial(block_size, class) as u128;
+    let mut k = class as u128;
+    let mut offset = 0;
+
+    for i in (0 .. block_size).rev() {
+        let zeros = total * (i as u128 + 1 - k) / (i as u128 + 1);
+        if block.get_bit(i) {
+            offset += zeros;
+            total -= zeros;
+            k -= 1;
+        } else {
+            total = zeros;
+        }
+    }
+
+    offset as u64
+}
+
+/// Decodes a block from its class and offset.
+pub fn decode(block_size: usize, class: usize, offset: u64) -> u64 {
+    let mut block = 0;
+    for (i, bit) in Scan::new(block_size, class, offset) {
+        if bit { block |= 1 << i }
+    }
+    block
+}
+
+/// The number of 1s at or below bit `position` of the encoded block.
+///
+/// # Panics
+///
+/// Debug mode only: panics if `position >= block_size`.
+pub fn rank(block_size: usize, class: usize, offset: u64, position: usize)
+            -> usize {
+    debug_assert!(position < block_size, "enum_code::rank: out of bounds");
+
+    let above = Scan::new(block_size, class, offset)
+        .take_while(|&(i, _)| i > position)
+        .filter(|&(_, bit)| bit)
+        .count();
+    class - above
+}
+
+/// The position of the `index`th 1 (counting from 0 at the low end) of
+/// the encoded block, if there are that many.
+pub fn select1(block_size: usize, class: usize, offset: u64, index: usize)
+               -> Option<usize> {
+    if index >= class { return None }
+
+    // Scanning from the top, it is the `class - index`th 1 we meet.
+    Scan::new(block_size, class, offset)
+        .filter(|&(_, bit)| bit)
+        .nth(class - 1 - index)
+        .map(|(i, _)| i)
+}
+
+/// The position of the `index`th 0 (counting from 0 at the low end) of
+/// the encoded block, if there are that many.
+pub fn select0(block_size: usize, class: usize, offset: u64, index: usize)
+               -> Option<usize> {
+    let zeros = block_size - class;
+    if index >= zeros { return None }
+
+    // Scanning from the top, it is the `zeros - index`th 0 we meet,
+    // unless it lies below the lowest 1, where the scan stops.
+    let mut seen = 0;
+    for (i, bit) in Scan::new(block_size, class, offset) {
+        if !bit {
+            if seen == zeros - 1 - index { return Some(i) }
+            seen += 1;
+        }
+    }
+
+    // Then the 0s below the lowest 1 are exactly the low positions.
+    Some(index)
+}
+
+// Yields the bits of an encoded block from the high bit down, stopping
+// after the last 1.
+struct Scan {
+    total: u128,
+    k: u128,
+    offset: u128,
+    i: usize,
+}
+
+impl Scan {
+    fn new(block_size: usize, class: usize, offset: u64) -> Self {
+        debug_assert!(block_size <= 64, "enum_code: block too large");
+        Scan {
+            total: binomial(block_size, class) as u128,
+            k: class as u128,
+            offset: offset as u128,
+            i: block_size,
+        }
+    }
+}
+
+impl Iterator for Scan {
+    type Item = (usize, bool);
+
+    fn next(&mut self) -> Option<(usize, bool)> {
+        if self.k == 0 || self.i == 0 { return None }
+
+        self.i -= 1;
+        let i = self.i as u128;
+        let zeros = self.total * (i + 1 - self.k) / (i + 1);
+        if self.offset >= zeros {
+            self.offset -= zeros;
+            self.total -= zeros;
+            self.k -= 1;
+            Some((self.i, true))
+        } else {
+            self.total = zeros;
+            Some((self.i, false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rank::BitRankSupport;
+
+    #[test]
+    fn binomials() {
+        assert_eq!(1, binomial(15, 0));
+        assert_eq!(15, binomial(15, 1));
+        assert_eq!(6435, binomial(15, 7));
+        assert_eq!(1, binomial(63, 63));
+        assert_eq!(916_312_070_471_295_267, binomial(63, 31));
+        assert_eq!(1_832_624_140_942_590_534, binomial(64, 32));
+    }
+
+    #[test]
+    fn block_coding() {
+        for block in 0 .. 1u64 << 10 {
+            let class = block.count_ones() as usize;
+            let offset = encode(10, class, block);
+            assert!(offset < binomial(10, class));
+            assert_eq!(block, decode(10, class, offset));
+        }
+
+        let block = 0x5555_5555_5555_5555u64 >> 1;
+        let offset = encode(63, 31, block);
+        assert_eq!(block, decode(63, 31, offset));
+
+        let block = 0xF0F0_F0F0_F0F0_F0F0u64;
+        let offset = encode(64, 32, block);
+        assert_eq!(block, decode(64, 32, offset));
+    }
+
+    #[test]
+    fn rank_select() {
+        for block in 0 .. 1u64 << 10 {
+            let class = block.count_ones() as usize;
+            let offset = encode(10, class, block);
+
+            for i in 0 .. 10 {
+                assert_eq!(block.rank1(i as u64) as usize,
+                           rank(10, class, offset, i));
+            }
+
+            for r in 0 .. 11 {
+                assert_eq!(block.select1(r), select1(10, class, offset, r));
+                assert_eq!((!block & 0x3FF).select1(r),
+                           select0(10, class, offset, r));
+            }
+        }
+    }
+}
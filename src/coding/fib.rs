@@ -5,8 +5,21 @@ use internal::errors::*;
 use stream::*;
 
 /// A Fibonacci code.
+#[derive(Clone, Copy, Debug)]
 pub struct Fibonacci;
 
+impl Fibonacci {
+    /// The largest value that can be encoded.
+    ///
+    /// This is one less than the largest Fibonacci number that fits in a
+    /// `u64`, since the encoder and decoder both need to compute the
+    /// next Fibonacci number above the largest one used. Encoding a
+    /// larger value returns an error without writing anything.
+    pub fn max_value() -> u64 {
+        12_200_160_415_121_876_737
+    }
+}
+
 struct Fib {
     i_1: u64,
     i: u64,
@@ -39,10 +52,11 @@ impl Fib {
 impl UniversalCode for Fibonacci {
     fn encode<W: BitWrite>(&self, sink: &mut W, mut value: u64) -> Result<()> {
         assert!(value != 0, "Fibonacci codes cannot handle 0.");
-        let mut fib = Fib::new();
+        if value > Self::max_value() {
+            return too_many_bits("Fibonacci::encode");
+        }
 
-        // Having to compute fib.i when we really just need fib.i_1
-        // means that this gives up on smaller numbers than it needs to.
+        let mut fib = Fib::new();
         while fib.i <= value {
             try!(fib.next());
         }
@@ -69,7 +83,7 @@ impl UniversalCode for Fibonacci {
     }
 
     fn decode<R: BitRead>(&self, source: &mut R) -> Result<Option<u64>> {
-        let mut result  = 0;
+        let mut result: u64 = 0;
         let mut fib = Fib::new();
         let mut previous = false;
 
@@ -79,7 +93,10 @@ impl UniversalCode for Fibonacci {
             }
 
             if bit {
-                result += fib.i;
+                result = match result.checked_add(fib.i) {
+                    Some(sum) => sum,
+                    None => return too_many_bits("Fibonacci::decode"),
+                };
             }
 
             try!(fib.next());
@@ -123,4 +140,30 @@ mod test {
 
         quickcheck(prop as fn(Vec<u64>) -> bool);
     }
+
+    #[test]
+    fn max_value() {
+        let max = Fibonacci::max_value();
+        let mut dv = VecDeque::<bool>::new();
+
+        Fibonacci.encode(&mut dv, max).unwrap();
+        Fibonacci.encode(&mut dv, max - 1).unwrap();
+        let len = dv.len();
+
+        assert!(Fibonacci.encode(&mut dv, max + 1).is_err());
+        assert!(Fibonacci.encode(&mut dv, u64::MAX).is_err());
+        assert_eq!(len, dv.len());
+
+        assert_eq!(Some(max), Fibonacci.decode(&mut dv).unwrap());
+        assert_eq!(Some(max - 1), Fibonacci.decode(&mut dv).unwrap());
+        assert_eq!(None::<u64>, Fibonacci.decode(&mut dv).unwrap());
+    }
+
+    #[test]
+    fn decode_overflow() {
+        // Alternating 1s followed by 11 add up to more than `u64::MAX`.
+        let mut dv: VecDeque<bool> =
+            (0 .. 92).map(|i| i % 2 == 1).chain(vec![true, true]).collect();
+        assert!(Fibonacci.decode(&mut dv).is_err());
+    }
 }
@@ -1,8 +1,9 @@
 //! Codes for data compression.
 //!
-//! These universal codes currently know how to encode to a `BitWrite`
-//! and decode from a `BitRead`. However, the code that would use them
-//! to implement compressed vectors and such isn’t written yet.
+//! These universal codes know how to encode to a `BitWrite` and decode
+//! from a `BitRead`. See
+//! [`CodedIntVec`](../int_vec/struct.CodedIntVec.html) for a compressed
+//! vector built on them.
 
 mod traits;
 pub use self::traits::*;
@@ -22,6 +23,17 @@ pub use self::comma::*;
 mod trans;
 pub use self::trans::*;
 
+mod vbyte;
+pub use self::vbyte::*;
+
+mod rle;
+pub use self::rle::*;
+
+pub mod rice;
+pub use self::rice::Rice;
+
+pub mod enum_code;
+
 #[cfg(test)]
 mod properties {
     use std::collections::VecDeque;
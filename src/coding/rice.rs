@@ -0,0 +1,152 @@
+//! Rice codes, and helpers for choosing their parameter.
+
+use std::f64::consts::LN_2;
+
+use super::*;
+use internal::errors::*;
+use stream::*;
+
+/// A Rice code encodes _n_ as _n_ >> _k_ in unary, followed by the low
+/// _k_ bits of _n_.
+///
+/// Rice codes suit geometrically distributed values; see
+/// [`optimal_k`](fn.optimal_k.html) for choosing _k_. With _k_ = 0
+/// this is just the unary code.
+#[derive(Clone, Copy, Debug)]
+pub struct Rice {
+    /// The number of low bits stored in binary.
+    pub k: u32,
+}
+
+impl UniversalCode for Rice {
+    fn encode<W: BitWrite>(&self, sink: &mut W, value: u64) -> Result<()> {
+        try!(Unary.encode(sink, value.checked_shr(self.k).unwrap_or(0)));
+        sink.write_int(self.k as usize, value)
+    }
+
+    fn decode<R: BitRead>(&self, source: &mut R) -> Result<Option<u64>> {
+        let quotient = match try!(Unary.decode(source)) {
+            Some(quotient) => quotient,
+            None => return Ok(None),
+        };
+
+        let low_bits = match try!(source.read_int::<u64>(self.k as usize)) {
+            Some(low_bits) => low_bits,
+            None => return out_of_bits("Rice::decode"),
+        };
+
+        if quotient > u64::MAX.checked_shr(self.k).unwrap_or(0) {
+            return too_many_bits("Rice::decode");
+        }
+
+        Ok(Some(quotient.checked_shl(self.k).unwrap_or(0) | low_bits))
+    }
+}
+
+/// Estimates the best Rice parameter *k* for values with the given
+/// mean.
+///
+/// This is the usual estimate ⌈log₂(ln(2) · mean)⌉, clamped at 0.
+pub fn optimal_k(mean: f64) -> u32 {
+    let estimate = (LN_2 * mean).log2().ceil();
+    if estimate > 0.0 {estimate as u32} else {0}
+}
+
+/// Estimates the best Rice parameter *k* for values like those in
+/// `sample`.
+///
+/// Returns 0 for an empty sample.
+pub fn optimal_k_for(sample: &[u64]) -> u32 {
+    if sample.is_empty() { return 0 }
+
+    let sum: f64 = sample.iter().map(|&value| value as f64).sum();
+    optimal_k(sum / sample.len() as f64)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use quickcheck::quickcheck;
+
+    use super::*;
+    use coding::properties;
+
+    #[test]
+    fn rice() {
+        let mut dv = VecDeque::<bool>::new();
+        let code = Rice { k: 2 };
+
+        code.encode(&mut dv, 0).unwrap();
+        code.encode(&mut dv, 7).unwrap();
+        assert_eq!(3 + 4, dv.len());
+
+        code.encode(&mut dv, 38932).unwrap();
+
+        assert_eq!(Some(0), code.decode(&mut dv).unwrap());
+        assert_eq!(Some(7), code.decode(&mut dv).unwrap());
+        assert_eq!(Some(38932), code.decode(&mut dv).unwrap());
+        assert_eq!(None, code.decode(&mut dv).unwrap());
+    }
+
+    #[test]
+    fn rice_extreme_k() {
+        let mut dv = VecDeque::<bool>::new();
+
+        Rice { k: 0 }.encode(&mut dv, 3).unwrap();
+        assert_eq!(4, dv.len());
+        assert_eq!(Some(3), Unary.decode(&mut dv).unwrap());
+
+        Rice { k: 64 }.encode(&mut dv, u64::MAX).unwrap();
+        Rice { k: 63 }.encode(&mut dv, u64::MAX).unwrap();
+        assert_eq!(Some(u64::MAX), Rice { k: 64 }.decode(&mut dv).unwrap());
+        assert_eq!(Some(u64::MAX), Rice { k: 63 }.decode(&mut dv).unwrap());
+    }
+
+    #[test]
+    fn rice_overflow() {
+        let mut dv = VecDeque::<bool>::new();
+        Unary.encode(&mut dv, 2).unwrap();
+        dv.extend(vec![false; 63]);
+
+        assert!(Rice { k: 63 }.decode(&mut dv).is_err());
+    }
+
+    #[test]
+    fn qc_rice() {
+        fn prop_rice(k: u8, v: Vec<u64>) -> bool {
+            properties::code_decode(&Rice { k: k as u32 % 8 }, v)
+        }
+
+        quickcheck(prop_rice as fn(u8, Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn small_means() {
+        assert_eq!(0, optimal_k(0.0));
+        assert_eq!(0, optimal_k(0.5));
+        assert_eq!(0, optimal_k(1.0));
+        assert_eq!(1, optimal_k(2.0));
+        assert_eq!(2, optimal_k(4.0));
+    }
+
+    #[test]
+    fn large_means() {
+        assert_eq!(3, optimal_k(10.0));
+        assert_eq!(7, optimal_k(100.0));
+        assert_eq!(10, optimal_k(1000.0));
+        assert_eq!(20, optimal_k(1_000_000.0));
+    }
+
+    #[test]
+    fn degenerate_means() {
+        assert_eq!(0, optimal_k(-5.0));
+        assert_eq!(0, optimal_k(f64::NAN));
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(0, optimal_k_for(&[]));
+        assert_eq!(0, optimal_k_for(&[0, 1, 0, 2]));
+        assert_eq!(optimal_k(100.0), optimal_k_for(&[50, 150, 90, 110]));
+    }
+}
@@ -0,0 +1,194 @@
+use std::cmp;
+
+use num_traits::{PrimInt, Zero};
+
+use super::*;
+use bit_vec::{BitVec, BitVector};
+use internal::errors::*;
+use storage::{Address, BlockType};
+use stream::*;
+
+/// Run-length encodes bit vectors, using another code for the lengths.
+///
+/// A bit vector is written as the lengths of its maximal runs, which
+/// alternate between 0s and 1s starting with 0s. Since the vector may
+/// start with a 1, the first length is encoded plus one; every later
+/// length is at least one anyway. So codes that can’t handle 0, like
+/// Elias codes, work without lifting.
+///
+/// The runs don’t record the total length, so it must be passed to
+/// `decode_bits`. An empty bit vector encodes as nothing.
+#[derive(Clone, Copy, Debug)]
+pub struct RunLength<Code: UniversalCode>(pub Code);
+
+impl<Code: UniversalCode> RunLength<Code> {
+    /// Writes the runs of `bits` to `sink`.
+    pub fn encode_bits<W, V>(&self, sink: &mut W, bits: &V) -> Result<()>
+        where W: BitWrite, V: BitVec
+    {
+        let len = bits.bit_len();
+        let mut position = 0;
+        let mut value = false;
+        let mut first = true;
+
+        while position < len {
+            let end = run_end(bits, position, value);
+            let run = end - position;
+            try!(self.0.encode(sink, if first {run + 1} else {run}));
+
+            position = end;
+            value = !value;
+            first = false;
+        }
+
+        Ok(())
+    }
+
+    /// Reads runs from `source` until they add up to `n` bits, appending
+    /// the bits to `out`.
+    ///
+    /// It is an error if `source` runs out first, or if the runs would
+    /// overshoot `n`.
+    pub fn decode_bits<R, Block>(&self, source: &mut R,
+                                 out: &mut BitVector<Block>, n: u64)
+                                 -> Result<()>
+        where R: BitRead, Block: BlockType
+    {
+        let mut remaining = n;
+        let mut value = false;
+        let mut first = true;
+
+        while remaining > 0 {
+            let run = match try!(self.0.decode(source)) {
+                Some(0) => return invalid_code("RunLength::decode_bits"),
+                Some(run) if first => run - 1,
+                Some(run) => run,
+                None => return out_of_bits("RunLength::decode_bits"),
+            };
+
+            if run > remaining {
+                return invalid_code("RunLength::decode_bits");
+            }
+
+            let start = out.bit_len();
+            out.resize(start + run, value);
+
+            remaining -= run;
+            value = !value;
+            first = false;
+        }
+
+        Ok(())
+    }
+}
+
+// The end of the run of `value`s starting at `start`.
+fn run_end<V: BitVec>(bits: &V, start: u64, value: bool) -> u64 {
+    let len = bits.bit_len();
+    let address = Address::new::<V::Block>(start);
+    let mut mask = !V::Block::low_mask(address.bit_offset);
+
+    for index in address.block_index .. bits.block_len() {
+        let block = bits.get_block(index);
+        let differs = if value {!block} else {block} & mask;
+        if differs != V::Block::zero() {
+            let end = V::Block::mul_nbits(index)
+                    + differs.trailing_zeros() as u64;
+            return cmp::min(len, end);
+        }
+
+        mask = !V::Block::zero();
+    }
+
+    len
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use quickcheck::quickcheck;
+
+    use bit_vec::{BitVec, BitVecMut, BitVector};
+    use coding::*;
+
+    fn round_trip<Code: UniversalCode>(code: &RunLength<Code>,
+                                       bits: &BitVector<u8>) -> bool {
+        let mut dv = VecDeque::<bool>::new();
+        code.encode_bits(&mut dv, bits).unwrap();
+        code.encode_bits(&mut dv, bits).unwrap();
+
+        let mut out = BitVector::new();
+        code.decode_bits(&mut dv, &mut out, bits.bit_len()).unwrap();
+        code.decode_bits(&mut dv, &mut out, bits.bit_len()).unwrap();
+
+        let mut expected = bits.clone();
+        expected.append(bits);
+        dv.is_empty() && out == expected
+    }
+
+    #[test]
+    fn qc_round_trip() {
+        fn prop(runs: Vec<(bool, u8)>) -> bool {
+            let mut bits = BitVector::new();
+            for (value, len) in runs {
+                let start = bits.bit_len();
+                bits.resize(start + len as u64, value);
+            }
+
+            round_trip(&RunLength(GAMMA), &bits)
+                && round_trip(&RunLength(Omega), &bits)
+                && round_trip(&RunLength(Unary), &bits)
+        }
+
+        quickcheck(prop as fn(Vec<(bool, u8)>) -> bool);
+    }
+
+    #[test]
+    fn extreme_runs() {
+        let code = RunLength(DELTA);
+
+        for &len in &[0, 1, 7, 8, 9, 100_000] {
+            assert!(round_trip(&code, &BitVector::with_fill(len, false)));
+            assert!(round_trip(&code, &BitVector::with_fill(len, true)));
+        }
+
+        let mut bits: BitVector<u8> = BitVector::with_fill(1_000_000, false);
+        bits.set_bit(0, true);
+        bits.set_bit(500_000, true);
+        bits.set_bit(999_999, true);
+        assert!(round_trip(&code, &bits));
+
+        let mut dv = VecDeque::<bool>::new();
+        code.encode_bits(&mut dv, &bits).unwrap();
+        assert!(dv.len() < 200);
+
+        let alternating: BitVector<u8> =
+            BitVector::from_ones((0 .. 1000).filter(|i| i % 2 == 1), 1000);
+        assert!(round_trip(&code, &alternating));
+    }
+
+    #[test]
+    fn runs() {
+        let bits: BitVector<u8> = BitVector::from_ones(vec![3, 4, 5, 9], 12);
+        let mut dv = VecDeque::<bool>::new();
+        RunLength(Unary).encode_bits(&mut dv, &bits).unwrap();
+
+        let mut runs = Vec::new();
+        Unary.decode_all(&mut dv, &mut runs, 10).unwrap();
+        assert_eq!(vec![4, 3, 3, 1, 2], runs);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let code = RunLength(GAMMA);
+        let mut out: BitVector<u8> = BitVector::new();
+
+        let mut dv = VecDeque::<bool>::new();
+        GAMMA.encode_all(&mut dv, &[3, 4]).unwrap();
+        assert!(code.decode_bits(&mut dv, &mut out, 5).is_err());
+
+        let mut dv = VecDeque::<bool>::new();
+        GAMMA.encode_all(&mut dv, &[3, 4]).unwrap();
+        assert!(code.decode_bits(&mut dv, &mut out, 7).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 pub use std::io::Result;
 
+use internal::counting::CountingWriter;
+use internal::errors::*;
 use stream::*;
 
 /// A universal code lets us encode arbitrary sized integers in a
@@ -13,5 +15,166 @@ pub trait UniversalCode {
     /// `Ok(None)` indicates (benign) EOF.
     fn decode<R: BitRead>(&self, source: &mut R) -> Result<Option<u64>>;
 
+    /// Writes each of `values` to `sink`, returning the total number of
+    /// bits written.
+    fn encode_all<W: BitWrite>(&self, sink: &mut W, values: &[u64])
+                               -> Result<usize> {
+        let mut counter = CountingWriter::new(sink);
+        for &value in values {
+            try!(self.encode(&mut counter, value));
+        }
+        Ok(counter.count)
+    }
+
+    /// Reads up to `n` values from `source`, appending them to `out`.
+    ///
+    /// Returns the number of values read, which is less than `n` only
+    /// if `source` reaches EOF first.
+    fn decode_all<R: BitRead>(&self, source: &mut R, out: &mut Vec<u64>,
+                              n: usize) -> Result<usize> {
+        out.reserve(n);
+        for i in 0 .. n {
+            match try!(self.decode(source)) {
+                Some(value) => out.push(value),
+                None => return Ok(i),
+            }
+        }
+        Ok(n)
+    }
+
+    /// Writes `value` to `sink`, encoded as `value + 1`.
+    ///
+    /// Many codes, such as the Elias codes, can’t represent 0. This
+    /// shifts every value up by one so that 0 can be encoded, at the
+    /// cost of `u64::MAX` becoming unencodable. Values written this way
+    /// must be read with `decode_nonneg`. Like `Lift0`, but without
+    /// wrapping the code.
+    fn encode_nonneg<W: BitWrite>(&self, sink: &mut W, value: u64)
+                                  -> Result<()> {
+        match value.checked_add(1) {
+            Some(lifted) => self.encode(sink, lifted),
+            None => too_many_bits("UniversalCode::encode_nonneg"),
+        }
+    }
+
+    /// Reads a value written by `encode_nonneg`, subtracting the 1 that
+    /// it added.
+    ///
+    /// `Ok(None)` indicates (benign) EOF. Decoding a raw 0 is an
+    /// error, since `encode_nonneg` never writes one.
+    fn decode_nonneg<R: BitRead>(&self, source: &mut R)
+                                 -> Result<Option<u64>> {
+        match try!(self.decode(source)) {
+            Some(0) => invalid_code("UniversalCode::decode_nonneg"),
+            Some(lifted) => Ok(Some(lifted - 1)),
+            None => Ok(None),
+        }
+    }
+
     // TODO: bigint support
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use quickcheck::quickcheck;
+    use coding::*;
+
+    // Values are kept small so that unary codes stay short, and
+    // shifted up by one for the codes that can’t handle 0.
+    fn prop_encode_decode_all<Code: UniversalCode>(code: &Code,
+                                                   values: &[u64]) -> bool {
+        let values: Vec<u64> = values.iter().map(|v| v % 1000 + 1).collect();
+
+        let mut expected_bits = 0;
+        for &value in &values {
+            let mut alone = VecDeque::<bool>::new();
+            code.encode(&mut alone, value).unwrap();
+            expected_bits += alone.len();
+        }
+
+        let mut dv = VecDeque::<bool>::new();
+        let bits = code.encode_all(&mut dv, &values).unwrap();
+
+        let mut out = vec![7];
+        let n = code.decode_all(&mut dv, &mut out, values.len() + 1)
+                    .unwrap();
+
+        bits == expected_bits && n == values.len()
+            && out[0] == 7 && out[1 ..] == values[..]
+    }
+
+    #[test]
+    fn encode_decode_all() {
+        fn prop(values: Vec<u64>) -> bool {
+            prop_encode_decode_all(&Unary, &values)
+                && prop_encode_decode_all(&GAMMA, &values)
+                && prop_encode_decode_all(&DELTA, &values)
+                && prop_encode_decode_all(&Omega, &values)
+                && prop_encode_decode_all(&Fibonacci, &values)
+                && prop_encode_decode_all(&COMMA, &values)
+                && prop_encode_decode_all(&Lift0(GAMMA), &values)
+                && prop_encode_decode_all(&Rice { k: 3 }, &values)
+                && prop_encode_decode_all(&VByte, &values)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn decode_all_partial() {
+        let mut dv = VecDeque::<bool>::new();
+        assert_eq!(12, GAMMA.encode_all(&mut dv, &[1, 2, 3, 4]).unwrap());
+
+        let mut out = Vec::new();
+        assert_eq!(2, GAMMA.decode_all(&mut dv, &mut out, 2).unwrap());
+        assert_eq!(vec![1, 2], out);
+        assert_eq!(2, GAMMA.decode_all(&mut dv, &mut out, 5).unwrap());
+        assert_eq!(vec![1, 2, 3, 4], out);
+        assert_eq!(0, GAMMA.decode_all(&mut dv, &mut out, 5).unwrap());
+    }
+
+    #[test]
+    fn nonneg() {
+        fn round_trip<Code: UniversalCode>(code: &Code, values: &[u64])
+                                           -> bool {
+            let mut dv = VecDeque::<bool>::new();
+            for &value in values {
+                code.encode_nonneg(&mut dv, value).unwrap();
+            }
+
+            let mut out = Vec::new();
+            while let Some(value) = code.decode_nonneg(&mut dv).unwrap() {
+                out.push(value);
+            }
+
+            out == values
+        }
+
+        fn prop(values: Vec<u64>) -> bool {
+            let mut values: Vec<u64> = values.iter().map(|v| v % 1000)
+                                             .collect();
+            values.push(0);
+
+            round_trip(&GAMMA, &values)
+                && round_trip(&DELTA, &values)
+                && round_trip(&Omega, &values)
+                && round_trip(&Fibonacci, &values)
+                && round_trip(&VByte, &values)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+
+        assert!(round_trip(&GAMMA, &[0, 1, 0, 5, 0]));
+        assert!(round_trip(&DELTA, &[u64::MAX - 1, 0]));
+        assert!(GAMMA.encode_nonneg(&mut VecDeque::<bool>::new(), u64::MAX)
+                     .is_err());
+    }
+
+    #[test]
+    fn decode_nonneg_zero() {
+        let mut dv = VecDeque::<bool>::new();
+        VByte.encode(&mut dv, 0).unwrap();
+        assert!(VByte.decode_nonneg(&mut dv).is_err());
+    }
+}
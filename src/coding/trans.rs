@@ -6,6 +6,7 @@ use stream::*;
 /// one from each decoded value.
 ///
 /// This is useful when the underlying code, like Elias codes, can’t handle 0s.
+#[derive(Clone, Copy, Debug)]
 pub struct Lift0<Code: UniversalCode>(pub Code);
 
 impl<Code: UniversalCode> UniversalCode for Lift0<Code> {
@@ -24,3 +25,129 @@ impl<Code: UniversalCode> UniversalCode for Lift0<Code> {
         }
     }
 }
+
+/// Adapts any code to signed integers by zigzag mapping them to
+/// unsigned: 0, -1, 1, -2, 2, … become 0, 1, 2, 3, 4, ….
+///
+/// Since 0 maps to 0, codes that can’t handle 0 should be lifted
+/// first, as in `Zigzag(Lift0(GAMMA))`.
+///
+/// The mapping is a bijection, so `i64::MIN` becomes `u64::MAX`, and
+/// the range that can be encoded depends on the inner code:
+///
+///   - Codes that handle every `u64`, such as `VByte` and `COMMA`,
+///     handle every `i64`.
+///   - `Zigzag(Lift0(GAMMA))`, `Zigzag(Lift0(DELTA))`, and the like
+///     handle every `i64` except `i64::MIN`, since `Lift0` can’t add
+///     one to `u64::MAX`.
+///   - `Zigzag(Lift0(Fibonacci))` handles only values with absolute
+///     value at most 6,100,080,207,560,938,368, since
+///     `Fibonacci::max_value()` is less than `u64::MAX`.
+///
+/// Encoding a value outside the range returns an `InvalidData` error
+/// without writing anything.
+#[derive(Clone, Copy, Debug)]
+pub struct Zigzag<Code: UniversalCode>(pub Code);
+
+impl<Code: UniversalCode> Zigzag<Code> {
+    /// Writes signed `value` to `sink`.
+    pub fn encode_signed<W: BitWrite>(&self, sink: &mut W, value: i64)
+                                      -> Result<()> {
+        self.0.encode(sink, ((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Reads a signed value from `source`.
+    ///
+    /// `Ok(None)` indicates (benign) EOF.
+    pub fn decode_signed<R: BitRead>(&self, source: &mut R)
+                                     -> Result<Option<i64>> {
+        Ok(try!(self.0.decode(source))
+               .map(|n| (n >> 1) as i64 ^ -((n & 1) as i64)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use quickcheck::quickcheck;
+
+    use coding::*;
+
+    fn round_trip<Code: UniversalCode>(code: &Zigzag<Code>, vec: &[i64])
+                                       -> bool {
+        let mut dv = VecDeque::<bool>::new();
+        for &i in vec {
+            code.encode_signed(&mut dv, i).unwrap();
+        }
+
+        let mut vec2 = Vec::<i64>::new();
+        while let Ok(Some(i)) = code.decode_signed(&mut dv) {
+            vec2.push(i);
+        }
+
+        vec2 == vec
+    }
+
+    #[test]
+    fn zigzag_mapping() {
+        let mut dv = VecDeque::<bool>::new();
+        for &i in &[0, -1, 1, -2, 2] {
+            Zigzag(Unary).encode_signed(&mut dv, i).unwrap();
+        }
+
+        for n in 0 .. 5 {
+            assert_eq!(Some(n), Unary.decode(&mut dv).unwrap());
+        }
+    }
+
+    #[test]
+    fn extremes() {
+        let vec = [i64::MIN, i64::MAX, 0, -1, 1, i64::MIN + 1, i64::MAX - 1];
+        assert!(round_trip(&Zigzag(VByte), &vec));
+        assert!(round_trip(&Zigzag(COMMA), &vec));
+    }
+
+    fn rejects<Code: UniversalCode>(code: &Zigzag<Code>, value: i64)
+                                    -> bool {
+        let mut dv = VecDeque::<bool>::new();
+        let result = code.encode_signed(&mut dv, value);
+        dv.is_empty() && result.map_err(|e| e.kind())
+            == Err(::std::io::ErrorKind::InvalidData)
+    }
+
+    #[test]
+    fn extremes_lifted() {
+        // Everything but `i64::MIN`.
+        let vec = [i64::MAX, i64::MIN + 1, 0, -1, 1, i64::MAX - 1];
+        assert!(round_trip(&Zigzag(Lift0(GAMMA)), &vec));
+        assert!(round_trip(&Zigzag(Lift0(DELTA)), &vec));
+        assert!(round_trip(&Zigzag(Lift0(Omega)), &vec));
+        assert!(rejects(&Zigzag(Lift0(GAMMA)), i64::MIN));
+        assert!(rejects(&Zigzag(Lift0(DELTA)), i64::MIN));
+        assert!(rejects(&Zigzag(Lift0(Omega)), i64::MIN));
+    }
+
+    #[test]
+    fn extremes_fibonacci() {
+        let code = Zigzag(Lift0(Fibonacci));
+        let max = 6_100_080_207_560_938_368;
+
+        assert!(round_trip(&code, &[max, -max, 0, max - 1, 1 - max]));
+        assert!(rejects(&code, max + 1));
+        assert!(rejects(&code, -max - 1));
+        assert!(rejects(&code, i64::MAX));
+        assert!(rejects(&code, i64::MIN));
+    }
+
+    #[test]
+    fn qc_zigzag() {
+        fn prop_zigzag(v: Vec<i64>) -> bool {
+            round_trip(&Zigzag(Lift0(GAMMA)), &v)
+                && round_trip(&Zigzag(Lift0(DELTA)), &v)
+                && round_trip(&Zigzag(Lift0(Fibonacci)), &v)
+                && round_trip(&Zigzag(Rice { k: 3 }), &v)
+        }
+
+        quickcheck(prop_zigzag as fn(Vec<i64>) -> bool);
+    }
+}
@@ -3,6 +3,7 @@ use internal::errors::*;
 use stream::*;
 
 /// Encodes _n_ as _n_ zeroes followed by a one.
+#[derive(Clone, Copy, Debug)]
 pub struct Unary;
 
 impl UniversalCode for Unary {
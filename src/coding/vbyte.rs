@@ -0,0 +1,156 @@
+use super::*;
+use internal::errors::*;
+use stream::*;
+
+/// Variable-byte code: 7 bits of the value per byte, least significant
+/// group first, with the high bit of each byte set when more bytes
+/// follow.
+///
+/// Every code word is a whole number of bytes, so VByte trades
+/// compression (at least 8 bits per value) for fast, byte-aligned
+/// decoding. Written to a `BitVector<u8>`, the blocks are the same
+/// bytes as Lucene’s `VInt`.
+///
+/// Byte alignment is preserved only if the stream starts aligned; use
+/// [`pad_to_byte`](#method.pad_to_byte) and
+/// [`skip_to_byte`](#method.skip_to_byte) after mixing in other codes.
+#[derive(Clone, Copy, Debug)]
+pub struct VByte;
+
+impl VByte {
+    /// Writes 0s to `sink` until `position`, the number of bits written
+    /// so far, reaches a byte boundary.
+    pub fn pad_to_byte<W: BitWrite>(sink: &mut W, position: u64)
+                                    -> Result<()> {
+        for _ in 0 .. padding(position) {
+            try!(sink.write_bit(false));
+        }
+
+        Ok(())
+    }
+
+    /// Skips bits from `source` until `position`, the number of bits
+    /// read so far, reaches a byte boundary.
+    pub fn skip_to_byte<R: BitRead>(source: &mut R, position: u64)
+                                    -> Result<()> {
+        for _ in 0 .. padding(position) {
+            if try!(source.read_bit()).is_none() {
+                return out_of_bits("VByte::skip_to_byte");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The number of bits from `position` to the next byte boundary.
+fn padding(position: u64) -> u64 {
+    (8 - position % 8) % 8
+}
+
+impl UniversalCode for VByte {
+    fn encode<W: BitWrite>(&self, sink: &mut W, mut value: u64) -> Result<()> {
+        while value >= 0x80 {
+            try!(sink.write_int(8, value & 0x7F | 0x80));
+            value >>= 7;
+        }
+
+        sink.write_int(8, value)
+    }
+
+    fn decode<R: BitRead>(&self, source: &mut R) -> Result<Option<u64>> {
+        let mut result = 0;
+        let mut shift = 0;
+
+        while let Some(byte) = try!(source.read_int::<u64>(8)) {
+            let group = byte & 0x7F;
+            if shift > 63 || (shift == 63 && group > 1) {
+                return too_many_bits("VByte::decode");
+            }
+
+            result |= group << shift;
+            if byte & 0x80 == 0 { return Ok(Some(result)) }
+            shift += 7;
+        }
+
+        if shift == 0 {
+            Ok(None)
+        } else {
+            out_of_bits("VByte::decode")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use quickcheck::quickcheck;
+
+    use bit_vec::{BitVec, BitVector};
+    use coding::*;
+    use stream::{BitBuffer, BitWrite};
+
+    #[test]
+    fn lengths() {
+        for bytes in 1 .. 11 {
+            let value = 1u64 << (7 * (bytes - 1));
+            let mut dv = VecDeque::<bool>::new();
+            VByte.encode(&mut dv, value).unwrap();
+            assert_eq!(8 * bytes, dv.len());
+            assert_eq!(Some(value), VByte.decode(&mut dv).unwrap());
+            assert_eq!(None, VByte.decode(&mut dv).unwrap());
+        }
+
+        let mut dv = VecDeque::<bool>::new();
+        VByte.encode(&mut dv, u64::MAX).unwrap();
+        assert_eq!(80, dv.len());
+        assert_eq!(Some(u64::MAX), VByte.decode(&mut dv).unwrap());
+    }
+
+    #[test]
+    fn bytes() {
+        let mut buffer: BitBuffer<BitVector<u8>> = BitBuffer::new();
+        VByte.encode(&mut buffer, 300).unwrap();
+
+        let bv = buffer.into_inner();
+        assert_eq!(vec![0xAC, 0x02],
+                   (0 .. bv.block_len()).map(|i| bv.get_block(i))
+                                        .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn padding() {
+        let mut buffer: BitBuffer<BitVector<u8>> = BitBuffer::new();
+        GAMMA.encode(&mut buffer, 5).unwrap();
+        let position = buffer.position();
+        VByte::pad_to_byte(&mut buffer, position).unwrap();
+        assert_eq!(8, buffer.position());
+        VByte.encode(&mut buffer, 1000).unwrap();
+
+        let mut buffer = BitBuffer::from(buffer.into_inner());
+        assert_eq!(Some(5), GAMMA.decode(&mut buffer).unwrap());
+        let position = buffer.position();
+        VByte::skip_to_byte(&mut buffer, position).unwrap();
+        assert_eq!(Some(1000), VByte.decode(&mut buffer).unwrap());
+    }
+
+    #[test]
+    fn too_long() {
+        let mut dv = VecDeque::<bool>::new();
+        for _ in 0 .. 10 {
+            dv.write_int(8, 0xFFu8).unwrap();
+        }
+        dv.write_int(8, 0x01u8).unwrap();
+
+        assert!(VByte.decode(&mut dv).is_err());
+    }
+
+    #[test]
+    fn qc_vbyte() {
+        fn prop_vbyte(v: Vec<u64>) -> bool {
+            properties::code_decode(&VByte, v)
+        }
+
+        quickcheck(prop_vbyte as fn(Vec<u64>) -> bool);
+    }
+}
@@ -0,0 +1,230 @@
+use std::cmp;
+
+use bit_vec::{BitSlice, BitVec, BitVector};
+use coding::UniversalCode;
+use int_vec::{IntVec, IntVector};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+use stream::BitBuffer;
+
+/// A vector of `u64`s compressed with a universal code.
+///
+/// The elements are encoded one after another, and the bit offset of
+/// every `stride`th element is sampled, so `get` decodes at most
+/// `stride` elements. Larger strides save space at the cost of slower
+/// random access.
+#[derive(Clone, Debug)]
+pub struct CodedIntVec<Code: UniversalCode> {
+    code: Code,
+    data: BitVector<u64>,
+    samples: IntVector<u64>,
+    stride: usize,
+    len: u64,
+}
+
+impl<Code: UniversalCode> CodedIntVec<Code> {
+    /// Encodes `values` with `code`, sampling every `stride`th offset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is 0, or if `code` can’t encode one of the
+    /// values.
+    pub fn from_iter<I>(code: Code, stride: usize, values: I) -> Self
+        where I: IntoIterator<Item = u64>
+    {
+        assert!(stride > 0, "CodedIntVec::from_iter: zero stride");
+
+        let mut buffer: BitBuffer<BitVector<u64>> = BitBuffer::new();
+        let mut offsets = Vec::new();
+        let mut len = 0;
+
+        for value in values {
+            if len % stride as u64 == 0 {
+                offsets.push(buffer.position());
+            }
+
+            code.encode(&mut buffer, value)
+                .expect("CodedIntVec::from_iter: could not encode");
+            len += 1;
+        }
+
+        let mut data = buffer.into_inner();
+        data.shrink_to_fit();
+        let offset_bits = cmp::max(1, (data.bit_len() + 1).ceil_lg());
+        let mut samples = IntVector::with_capacity(offset_bits,
+                                                   offsets.len() as u64);
+        for offset in offsets {
+            samples.push(offset);
+        }
+
+        CodedIntVec {
+            code,
+            data,
+            samples,
+            stride,
+            len,
+        }
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the vector empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The sampling stride.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Borrows the code used to encode the elements.
+    pub fn code(&self) -> &Code {
+        &self.code
+    }
+
+    /// Gets the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: u64) -> u64 {
+        assert!(index < self.len, "CodedIntVec::get: out of bounds");
+
+        let sample = index / self.stride as u64;
+        let mut iter = self.iter_from_sample(sample);
+        for _ in sample * self.stride as u64 .. index {
+            iter.next();
+        }
+
+        iter.next().expect("CodedIntVec::get: could not decode")
+    }
+
+    /// Gets an iterator over the elements.
+    pub fn iter(&self) -> CodedIter<'_, Code> {
+        self.iter_from_sample(0)
+    }
+
+    fn iter_from_sample(&self, sample: u64) -> CodedIter<'_, Code> {
+        let start = if self.is_empty() {0} else {self.samples.get(sample)};
+
+        CodedIter {
+            code: &self.code,
+            buffer: BitBuffer::from(BitSlice::new(&self.data, start ..)),
+            remaining: self.len - sample * self.stride as u64,
+        }
+    }
+}
+
+impl<Code: UniversalCode> SpaceUsage for CodedIntVec<Code> {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.data.heap_bytes() + self.samples.heap_bytes()
+    }
+}
+
+impl<'a, Code: UniversalCode> IntoIterator for &'a CodedIntVec<Code> {
+    type Item = u64;
+    type IntoIter = CodedIter<'a, Code>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a `CodedIntVec`.
+pub struct CodedIter<'a, Code: 'a + UniversalCode> {
+    code: &'a Code,
+    buffer: BitBuffer<BitSlice<'a, BitVector<u64>>>,
+    remaining: u64,
+}
+
+impl<'a, Code: 'a + UniversalCode> Iterator for CodedIter<'a, Code> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 { return None }
+
+        self.remaining -= 1;
+        let value = self.code.decode(&mut self.buffer)
+                             .expect("CodedIter::next: could not decode");
+        Some(value.expect("CodedIter::next: unexpected end of data"))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining as usize;
+        (len, Some(len))
+    }
+}
+
+impl<'a, Code: 'a + UniversalCode> ExactSizeIterator for CodedIter<'a, Code> {}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::quickcheck;
+
+    use coding::*;
+    use space_usage::SpaceUsage;
+    use super::*;
+
+    fn matches_vec<Code: UniversalCode>(code: Code, stride: usize,
+                                        values: &[u64]) -> bool {
+        let coded = CodedIntVec::from_iter(code, stride,
+                                           values.iter().cloned());
+
+        coded.len() == values.len() as u64
+            && coded.iter().eq(values.iter().cloned())
+            && (0 .. values.len()).all(|i| coded.get(i as u64) == values[i])
+    }
+
+    fn prop(stride: u8, values: Vec<u64>) -> bool {
+        let stride = stride as usize % 10 + 1;
+
+        matches_vec(Lift0(GAMMA), stride, &values)
+            && matches_vec(Lift0(DELTA), stride, &values)
+            && matches_vec(Lift0(Omega), stride, &values)
+            && matches_vec(Rice { k: 2 }, stride, &values)
+            && matches_vec(VByte, stride, &values)
+    }
+
+    #[test]
+    fn qc() {
+        quickcheck(prop as fn(u8, Vec<u64>) -> bool);
+    }
+
+    #[test]
+    fn empty() {
+        let coded = CodedIntVec::from_iter(VByte, 4, None);
+        assert!(coded.is_empty());
+        assert_eq!(None, coded.iter().next());
+    }
+
+    #[test]
+    #[should_panic]
+    fn get_oob() {
+        let coded = CodedIntVec::from_iter(VByte, 4, vec![1, 2, 3]);
+        coded.get(3);
+    }
+
+    #[test]
+    fn space() {
+        let values: Vec<u64> = (0 .. 1000).map(|i| i % 10).collect();
+        let coded = CodedIntVec::from_iter(Lift0(GAMMA), 32, values);
+
+        assert_eq!(32, coded.stride());
+        assert!(coded.heap_bytes() < 1000);
+        assert!(coded.heap_bytes() >= coded.data.bit_len() as usize / 8);
+    }
+
+    #[test]
+    fn clone() {
+        let coded = CodedIntVec::from_iter(Lift0(GAMMA), 4, vec![3, 0, 9])
+            .clone();
+        assert_eq!(9, coded.get(2));
+        assert!(format!("{:?}", coded).starts_with("CodedIntVec"));
+    }
+}
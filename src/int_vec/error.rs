@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when a value doesn’t fit in an integer vector’s
+/// element width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ValueTooLarge<Block = usize> {
+    /// The rejected value.
+    pub value: Block,
+    /// The element width it didn’t fit in.
+    pub element_bits: usize,
+}
+
+impl<Block: fmt::Display> fmt::Display for ValueTooLarge<Block> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "value {} does not fit in {} bits",
+               self.value, self.element_bits)
+    }
+}
+
+impl<Block: fmt::Debug + fmt::Display> Error for ValueTooLarge<Block> {}
+
+/// The error returned by
+/// [`IntVector::set_checked`](struct.IntVector.html#method.set_checked).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SetError<Block = usize> {
+    /// The index was not less than the vector’s length.
+    OutOfBounds {
+        /// The rejected index.
+        index: u64,
+        /// The length of the vector.
+        len: u64,
+    },
+    /// The value didn’t fit in the element width.
+    TooLarge(ValueTooLarge<Block>),
+}
+
+impl<Block> From<ValueTooLarge<Block>> for SetError<Block> {
+    fn from(error: ValueTooLarge<Block>) -> Self {
+        SetError::TooLarge(error)
+    }
+}
+
+impl<Block: fmt::Display> fmt::Display for SetError<Block> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetError::OutOfBounds { index, len } =>
+                write!(formatter, "index {} out of bounds for length {}",
+                       index, len),
+            SetError::TooLarge(ref error) => error.fmt(formatter),
+        }
+    }
+}
+
+impl<Block: fmt::Debug + fmt::Display> Error for SetError<Block> {}
@@ -0,0 +1,187 @@
+use int_vec::{IntVec, IntVecMut, IntVector};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A Fenwick tree (binary indexed tree) of non-negative integers,
+/// bit-packed into an `IntVector`.
+///
+/// Supports adding to an element and querying a prefix sum, each in
+/// *O*(lg *n*) time.
+///
+/// Each node stores the sum of a range of elements, and the last node
+/// stores the sum of the largest power-of-two-sized prefix. So the
+/// element width must be enough for the largest prefix sum you expect:
+/// `element_bits` should be at least `ceil_lg(max_total + 1)`. An `add`
+/// that would overflow a node panics without changing the tree.
+#[derive(Clone, Debug)]
+pub struct Fenwick {
+    nodes: IntVector<u64>,
+}
+
+impl Fenwick {
+    /// Creates a tree of `n` zeros, with nodes `element_bits` wide.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= element_bits <= 64`.
+    pub fn with_width(n: u64, element_bits: usize) -> Self {
+        Fenwick {
+            nodes: IntVector::with_fill(element_bits, n, 0),
+        }
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> u64 {
+        self.nodes.len()
+    }
+
+    /// Is the tree empty?
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The width of each node in bits.
+    pub fn element_bits(&self) -> usize {
+        self.nodes.element_bits()
+    }
+
+    /// Adds `delta` to the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if some prefix sum would
+    /// no longer fit in `element_bits` bits.
+    pub fn add(&mut self, index: u64, delta: u64) {
+        assert!(index < self.len(), "Fenwick::add: out of bounds");
+
+        let max = u64::low_mask(self.element_bits());
+        let fits = self.updated_nodes(index).all(|node| {
+            match self.nodes.get(node).checked_add(delta) {
+                Some(sum) => sum <= max,
+                None => false,
+            }
+        });
+        assert!(fits, "Fenwick::add: overflow");
+
+        for node in self.updated_nodes(index) {
+            let value = self.nodes.get(node);
+            self.nodes.set(node, value + delta);
+        }
+    }
+
+    /// Returns the sum of the elements from 0 up to and including
+    /// `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn prefix_sum(&self, index: u64) -> u64 {
+        assert!(index < self.len(), "Fenwick::prefix_sum: out of bounds");
+
+        let mut sum = 0;
+        let mut position = index + 1;
+        while position > 0 {
+            sum += self.nodes.get(position - 1);
+            position &= position - 1;
+        }
+
+        sum
+    }
+
+    // The nodes that cover `index`, which are the ones `add` changes.
+    fn updated_nodes(&self, index: u64) -> UpdatedNodes {
+        UpdatedNodes {
+            position: index + 1,
+            len: self.len(),
+        }
+    }
+}
+
+struct UpdatedNodes {
+    position: u64,
+    len: u64,
+}
+
+impl Iterator for UpdatedNodes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.position > self.len { return None; }
+
+        let result = self.position - 1;
+        self.position += self.position & self.position.wrapping_neg();
+        Some(result)
+    }
+}
+
+impl SpaceUsage for Fenwick {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.nodes.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn fenwick_prop(len: u8, updates: Vec<(u8, u8)>) -> bool {
+        let len = len as u64 + 1;
+        let mut fenwick = Fenwick::with_width(len, 20);
+        let mut naive = vec![0u64; len as usize];
+
+        updates.into_iter().all(|(index, delta)| {
+            let index = index as u64 % len;
+            fenwick.add(index, delta as u64);
+            naive[index as usize] += delta as u64;
+
+            let mut sum = 0;
+            naive.iter().enumerate().all(|(i, &value)| {
+                sum += value;
+                fenwick.prefix_sum(i as u64) == sum
+            })
+        })
+    }
+
+    #[test]
+    fn prefix_sum_qc() {
+        quickcheck(fenwick_prop as fn(u8, Vec<(u8, u8)>) -> bool);
+    }
+
+    #[test]
+    fn add() {
+        let mut fenwick = Fenwick::with_width(10, 8);
+        fenwick.add(3, 5);
+        fenwick.add(0, 1);
+        fenwick.add(9, 100);
+
+        assert_eq!(1, fenwick.prefix_sum(0));
+        assert_eq!(1, fenwick.prefix_sum(2));
+        assert_eq!(6, fenwick.prefix_sum(3));
+        assert_eq!(6, fenwick.prefix_sum(8));
+        assert_eq!(106, fenwick.prefix_sum(9));
+    }
+
+    #[test]
+    fn overflow_leaves_tree_unchanged() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut fenwick = Fenwick::with_width(8, 4);
+        fenwick.add(0, 10);
+        fenwick.add(5, 5);
+
+        // Node 7 holds the total, so this overflows there.
+        let result = catch_unwind(AssertUnwindSafe(|| fenwick.add(1, 1)));
+        assert!(result.is_err());
+        assert_eq!(10, fenwick.prefix_sum(1));
+        assert_eq!(15, fenwick.prefix_sum(7));
+    }
+
+    #[test]
+    #[should_panic]
+    fn oob() {
+        Fenwick::with_width(8, 4).prefix_sum(8);
+    }
+}
@@ -0,0 +1,141 @@
+use int_vec::{IntVec, IntVector, Iter};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A set of integers, stored sorted in an `IntVector` just wide enough
+/// for the largest.
+///
+/// Membership and rank queries binary search the elements, taking
+/// *O*(lg *n*) time.
+#[derive(Clone, Debug)]
+pub struct IntSet<Block: BlockType = usize> {
+    elements: IntVector<Block>,
+}
+
+impl<Block: BlockType> IntSet<Block> {
+    /// Creates a set from sorted, distinct values.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `values` is strictly increasing.
+    pub fn new(values: &[Block]) -> Self {
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]),
+                "IntSet::new: values not sorted and distinct");
+
+        let element_bits = match values.last() {
+            Some(&max) if max != Block::zero() => max.floor_lg() + 1,
+            _ => 1,
+        };
+
+        let mut elements = IntVector::with_capacity(element_bits,
+                                                    values.len() as u64);
+        elements.extend_from_slice(values);
+
+        IntSet { elements }
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> u64 {
+        self.elements.len()
+    }
+
+    /// Is the set empty?
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// The width of the stored elements.
+    pub fn element_bits(&self) -> usize {
+        self.elements.element_bits()
+    }
+
+    /// Is `value` in the set?
+    pub fn contains(&self, value: Block) -> bool {
+        let index = self.rank(value);
+        index < self.len() && self.elements.get(index) == value
+    }
+
+    /// The number of elements less than `value`.
+    ///
+    /// If `value` is in the set, this is its index in `iter()`.
+    pub fn rank(&self, value: Block) -> u64 {
+        let mut low = 0;
+        let mut high = self.len();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.elements.get(mid) < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        low
+    }
+
+    /// Gets an iterator over the elements in increasing order.
+    pub fn iter(&self) -> Iter<'_, Block> {
+        self.elements.iter()
+    }
+}
+
+impl<Block: BlockType> SpaceUsage for IntSet<Block> {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.elements.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use quickcheck::quickcheck;
+
+    use super::*;
+
+    #[test]
+    fn matches_btree_set() {
+        fn prop(values: Vec<u16>, queries: Vec<u16>) -> bool {
+            let expected: BTreeSet<u16> = values.into_iter().collect();
+            let sorted: Vec<u16> = expected.iter().cloned().collect();
+            let set = IntSet::new(&sorted);
+
+            set.len() == expected.len() as u64
+                && set.iter().eq(expected.iter().cloned())
+                && queries.iter().all(|&x| {
+                    set.contains(x) == expected.contains(&x)
+                        && set.rank(x) == expected.range(.. x).count() as u64
+                })
+        }
+
+        quickcheck(prop as fn(Vec<u16>, Vec<u16>) -> bool);
+    }
+
+    #[test]
+    fn element_bits() {
+        assert_eq!(1, IntSet::<u32>::new(&[]).element_bits());
+        assert_eq!(1, IntSet::<u32>::new(&[0]).element_bits());
+        assert_eq!(1, IntSet::<u32>::new(&[0, 1]).element_bits());
+        assert_eq!(10, IntSet::<u32>::new(&[3, 1023]).element_bits());
+        assert_eq!(11, IntSet::<u32>::new(&[3, 1024]).element_bits());
+        assert_eq!(64, IntSet::<u64>::new(&[u64::MAX]).element_bits());
+    }
+
+    #[test]
+    fn rank_past_end() {
+        let set = IntSet::<u8>::new(&[2, 4, 6]);
+        assert_eq!(0, set.rank(0));
+        assert_eq!(1, set.rank(3));
+        assert_eq!(2, set.rank(6));
+        assert_eq!(3, set.rank(255));
+        assert!(!set.contains(255));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        IntSet::<u8>::new(&[1, 3, 3]);
+    }
+}
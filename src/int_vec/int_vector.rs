@@ -1,10 +1,19 @@
+use std::cmp;
 use std::fmt;
+use std::io::Result;
+
+use num_traits::cast;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize};
 
 use super::*;
 use bit_vec::{BitVec, BitVecMut};
+use coding::UniversalCode;
+use internal::errors::too_many_bits;
 use internal::vector_base::{VectorBase, self};
 use space_usage::SpaceUsage;
 use storage::BlockType;
+use stream::{BitRead, BitWrite};
 
 /// Uncompressed vector of *k*-bit unsigned integers.
 ///
@@ -13,6 +22,7 @@ use storage::BlockType;
 /// `Block` gives the representation type. The element width *k* can
 /// never exceed the number of bits in `Block`.
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct IntVector<Block: BlockType = usize> {
     element_bits: usize,
     base: VectorBase<Block>,
@@ -36,6 +46,15 @@ impl<Block: BlockType> IntVector<Block> {
         Self::check_value_random(self.element_bits, element_value);
     }
 
+    /// The smallest element size that can hold `value`.
+    fn bits_needed(value: Block) -> usize {
+        if value == Block::zero() {
+            1
+        } else {
+            value.floor_lg() + 1
+        }
+    }
+
     /// Checks `element_bits` before assembling an `IntVector`.
     fn create(element_bits: usize, base: VectorBase<Block>) -> Self {
         Self::check_element_bits(element_bits);
@@ -109,6 +128,24 @@ impl<Block: BlockType> IntVector<Block> {
                                                  value))
     }
 
+    /// Creates an integer vector of `len` elements from already packed
+    /// blocks, taking ownership of `blocks` without copying.
+    ///
+    /// Element `i` occupies bits `i * element_bits ..` of the blocks,
+    /// as in the vector’s own representation. Blocks past the last
+    /// element are dropped, and bits past it in the last block are
+    /// cleared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `element_bits` is invalid or if `blocks` is too short
+    /// to hold `len` elements.
+    pub fn from_blocks(element_bits: usize, len: u64, blocks: Vec<Block>)
+                       -> Self {
+        Self::create(element_bits,
+                     VectorBase::from_blocks(element_bits, len, blocks))
+    }
+
     /// Returns the element at a given index, also given an arbitrary
     /// element size and bit offset.
     ///
@@ -166,6 +203,65 @@ impl<Block: BlockType> IntVector<Block> {
         self.base.push_bits(self.element_bits, element_value);
     }
 
+    /// Pushes an element onto the end of the vector, unless it is too
+    /// large for the element width.
+    ///
+    /// Unlike `push`, this doesn’t panic on a bad value, which makes it
+    /// suitable for untrusted input. On error the vector is unchanged.
+    pub fn try_push(&mut self, element_value: Block)
+                    -> ::std::result::Result<(), ValueTooLarge<Block>> {
+        if element_value <= Block::low_mask(self.element_bits) {
+            self.base.push_bits(self.element_bits, element_value);
+            Ok(())
+        } else {
+            Err(ValueTooLarge {
+                value: element_value,
+                element_bits: self.element_bits,
+            })
+        }
+    }
+
+    /// Appends all the elements of a slice to the vector.
+    ///
+    /// When the element width divides the block size and the vector
+    /// currently ends on a block boundary, the elements are packed into
+    /// whole blocks before being appended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any value is too large for the element width, in which
+    /// case the vector is unchanged.
+    pub fn extend_from_slice(&mut self, values: &[Block]) {
+        for &value in values {
+            self.check_value(value);
+        }
+
+        self.reserve_exact(values.len() as u64);
+
+        let element_bits = self.element_bits;
+        let per_block = Block::nbits() / element_bits;
+        let mut rest = values;
+
+        if per_block * element_bits == Block::nbits()
+            && Block::mod_nbits(self.bit_len()) == 0 {
+            let chunks = values.chunks_exact(per_block);
+            rest = chunks.remainder();
+
+            let blocks = chunks.map(|chunk| {
+                let mut block = Block::zero();
+                for (i, &value) in chunk.iter().enumerate() {
+                    block = block | value << (i * element_bits);
+                }
+                block
+            });
+            self.base.extend_blocks(element_bits, blocks);
+        }
+
+        for &value in rest {
+            self.base.push_bits(element_bits, value);
+        }
+    }
+
     /// Removes and returns the last element of the vector, if present.
     pub fn pop(&mut self) -> Option<Block> {
         self.base.pop_bits(self.element_bits)
@@ -187,6 +283,27 @@ impl<Block: BlockType> IntVector<Block> {
         self.base.resize(self.element_bits, n_elements, fill);
     }
 
+    /// Resizes to the given number of elements, calling `f` to produce
+    /// each new element if growing.
+    ///
+    /// When shrinking, `f` is never called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` returns a value too large for the element size.
+    pub fn resize_with<F: FnMut() -> Block>(&mut self, n_elements: u64,
+                                            mut f: F) {
+        if n_elements <= self.len() {
+            self.truncate(n_elements);
+            return;
+        }
+
+        self.reserve_exact(n_elements - self.len());
+        while self.len() < n_elements {
+            self.push(f());
+        }
+    }
+
     /// Resizes to the given number of blocks, filling if necessary.
     pub fn block_resize(&mut self, n_blocks: usize, fill: Block) {
         self.base.block_resize(self.element_bits, n_blocks, fill);
@@ -254,6 +371,8 @@ impl<Block: BlockType> IntVector<Block> {
     }
 
     /// Shrinks the capacity to just fit the number of elements.
+    ///
+    /// Afterward, `block_capacity()` equals `block_len()`.
     pub fn shrink_to_fit(&mut self) {
         self.base.shrink_to_fit();
     }
@@ -277,11 +396,483 @@ impl<Block: BlockType> IntVector<Block> {
         self.base.clear();
     }
 
+    /// Keeps only the elements for which `f` returns true, preserving
+    /// their order.
+    pub fn retain<F: FnMut(Block) -> bool>(&mut self, mut f: F) {
+        let mut write = 0;
+
+        for read in 0 .. self.len() {
+            let element = self.get(read);
+            if f(element) {
+                if write != read {
+                    self.set(write, element);
+                }
+                write += 1;
+            }
+        }
+
+        self.truncate(write);
+    }
+
+    /// Replaces each element with the result of applying `f` to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` returns a value too large for the element width.
+    /// Elements before that one will already have been replaced.
+    pub fn map_in_place<F: FnMut(Block) -> Block>(&mut self, mut f: F) {
+        for i in 0 .. self.len() {
+            let element = self.get(i);
+            self.set(i, f(element));
+        }
+    }
+
+    /// Returns the first element, or `None` if the vector is empty.
+    pub fn first(&self) -> Option<Block> {
+        if self.is_empty() {None} else {Some(self.get(0))}
+    }
+
+    /// Returns the last element, or `None` if the vector is empty.
+    pub fn last(&self) -> Option<Block> {
+        if self.is_empty() {None} else {Some(self.get(self.len() - 1))}
+    }
+
+    /// Returns the element at `index`, or `None` if `index` is out of
+    /// bounds.
+    pub fn get_checked(&self, index: u64) -> Option<Block> {
+        if index < self.len() {Some(self.get(index))} else {None}
+    }
+
+    /// Sets the element at `index` to `value`, unless `index` is out of
+    /// bounds or `value` doesn’t fit in `element_bits` bits.
+    ///
+    /// On failure the vector is unchanged, and the error says which
+    /// check failed. An out-of-bounds index is reported even if the
+    /// value is also too large.
+    pub fn set_checked(&mut self, index: u64, value: Block)
+                       -> ::std::result::Result<(), SetError<Block>> {
+        if index >= self.len() {
+            Err(SetError::OutOfBounds { index, len: self.len() })
+        } else if value > Block::low_mask(self.element_bits) {
+            Err(SetError::TooLarge(ValueTooLarge {
+                value,
+                element_bits: self.element_bits,
+            }))
+        } else {
+            self.set(index, value);
+            Ok(())
+        }
+    }
+
+    /// Swaps the elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: u64, j: u64) {
+        assert!(i < self.len() && j < self.len(),
+                "IntVector::swap: out of bounds");
+
+        let a = self.get(i);
+        let b = self.get(j);
+        self.set(i, b);
+        self.set(j, a);
+    }
+
+    /// Removes and returns the element at `index`, replacing it with the
+    /// last element.
+    ///
+    /// This doesn’t preserve the order of the elements, but takes
+    /// constant time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: u64) -> Block {
+        assert!(index < self.len(), "IntVector::swap_remove: out of bounds");
+
+        let result = self.get(index);
+        let last = self.pop().expect("IntVector::swap_remove: empty");
+        if index < self.len() {
+            self.set(index, last);
+        }
+
+        result
+    }
+
+    /// Reverses the order of the elements in place.
+    pub fn reverse(&mut self) {
+        let len = self.len();
+
+        for i in 0 .. len / 2 {
+            let j = len - 1 - i;
+            let front = self.get(i);
+            let back = self.get(j);
+            self.set(i, back);
+            self.set(j, front);
+        }
+    }
+
+    /// Returns the smallest element, or `None` if the vector is empty.
+    pub fn min_element(&self) -> Option<Block> {
+        Iterator::min(self.iter())
+    }
+
+    /// Returns the largest element, or `None` if the vector is empty.
+    pub fn max_element(&self) -> Option<Block> {
+        Iterator::max(self.iter())
+    }
+
+    /// Sums the elements as `u64`s, so that the sum doesn’t overflow
+    /// `Block`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sum overflows `u64`.
+    pub fn sum_u64(&self) -> u64 {
+        self.iter().fold(0u64, |sum, element| {
+            let element = element.to_u64()
+                .expect("IntVector::sum_u64: element too large");
+            sum.checked_add(element)
+               .expect("IntVector::sum_u64: overflow")
+        })
+    }
+
+    /// Sorts the elements in increasing order.
+    ///
+    /// The elements are unpacked into a `Vec`, sorted with an unstable
+    /// sort, and written back.
+    pub fn sort(&mut self) {
+        let mut elements: Vec<Block> = self.iter().collect();
+        elements.sort_unstable();
+
+        for (i, element) in elements.into_iter().enumerate() {
+            self.set(i as u64, element);
+        }
+    }
+
+    /// Are the elements in (non-strictly) increasing order?
+    pub fn is_sorted(&self) -> bool {
+        (1 .. self.len()).all(|i| self.get(i - 1) <= self.get(i))
+    }
+
     /// Gets an iterator over the elements of the vector.
     pub fn iter(&self) -> Iter<Block> {
         Iter(vector_base::Iter::new(self.element_bits, &self.base))
     }
 
+    /// Gets an iterator over the `len` elements starting at index
+    /// `start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range goes out of bounds.
+    pub fn iter_range(&self, start: u64, len: u64) -> Iter<'_, Block> {
+        let limit = start.checked_add(len)
+                         .filter(|&limit| limit <= self.len())
+                         .expect("IntVector::iter_range: out of bounds");
+
+        Iter(vector_base::Iter::with_range(self.element_bits, &self.base,
+                                           start, limit))
+    }
+
+    /// Gets an iterator over the underlying bits, regrouped into chunks
+    /// of `new_element_bits` bits.
+    ///
+    /// This ignores the original element boundaries: chunk `i` is bits
+    /// `i * new_element_bits` up to `(i + 1) * new_element_bits` of the
+    /// vector, least significant first. If the bit length isn’t a
+    /// multiple of `new_element_bits` then the last chunk is short, and
+    /// its missing high bits are 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= new_element_bits <= Block::nbits()`.
+    pub fn reinterpret_iter(&self, new_element_bits: usize)
+                            -> impl Iterator<Item = Block> + '_ {
+        Self::check_element_bits(new_element_bits);
+
+        let bit_len = self.bit_len();
+        let chunk_bits = new_element_bits as u64;
+
+        (0 .. bit_len.ceil_div(chunk_bits)).map(move |i| {
+            let start = i * chunk_bits;
+            let count = cmp::min(chunk_bits, bit_len - start);
+            self.get_bits(start, count as usize)
+        })
+    }
+
+    /// Copies the elements into a new vector with a different element
+    /// width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_element_bits` is invalid, or if some element
+    /// doesn’t fit in `new_element_bits` bits.
+    pub fn repack(&self, new_element_bits: usize) -> Self {
+        let mut result = IntVector::with_capacity(new_element_bits,
+                                                  self.len());
+        for element in self {
+            result.push(element);
+        }
+
+        result
+    }
+
+    /// Copies the elements into a new vector whose element width is the
+    /// smallest that holds the largest element.
+    ///
+    /// An empty or all-zero vector yields 1-bit elements.
+    pub fn compress_width(&self) -> Self {
+        let max = self.max_element().unwrap_or(Block::zero());
+        self.repack(Self::bits_needed(max))
+    }
+
+    /// Replaces each element `e` with `table[e]`.
+    ///
+    /// The result’s element size is the smallest that can hold the
+    /// largest value in `table`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any element is not a valid index into `table`.
+    pub fn gather(&self, table: &[Block]) -> Self {
+        let max = table.iter().cloned().max().unwrap_or(Block::zero());
+        let mut result = IntVector::with_capacity(Self::bits_needed(max),
+                                                  self.len());
+
+        for element in self {
+            let index = element.to_usize()
+                               .filter(|&index| index < table.len())
+                               .expect("IntVector::gather: index out of bounds");
+            result.push(table[index]);
+        }
+
+        result
+    }
+
+    /// Appends the elements of `other` to the end of this vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vectors’ element widths differ.
+    pub fn append(&mut self, other: &IntVector<Block>) {
+        assert_eq!(self.element_bits, other.element_bits,
+                   "IntVector::append: element widths differ");
+
+        self.reserve(other.len());
+
+        let mut start = 0;
+
+        // If the seam falls on a block boundary then we can copy
+        // `other`’s full blocks directly.
+        if Block::mod_nbits(self.bit_len()) == 0 {
+            let full_blocks = Block::div_nbits(other.bit_len());
+            let len_before = self.len();
+
+            self.base.extend_blocks(self.element_bits,
+                                    (0 .. full_blocks)
+                                        .map(|i| other.get_block(i)));
+
+            start = self.len() - len_before;
+        }
+
+        for i in start .. other.len() {
+            self.push(other.get(i));
+        }
+    }
+
+    /// Concatenates several vectors of the same element width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parts` is empty or if the parts’ element widths
+    /// differ.
+    pub fn concat(parts: &[IntVector<Block>]) -> Self {
+        assert!(!parts.is_empty(), "IntVector::concat: no parts");
+
+        let element_bits = parts[0].element_bits;
+        let total = parts.iter().map(|part| part.len()).sum();
+        let mut result = IntVector::with_capacity(element_bits, total);
+
+        for part in parts {
+            result.append(part);
+        }
+
+        result
+    }
+
+    /// Splits the vector in two at the given index.
+    ///
+    /// Returns a new vector containing the elements `at ..`, leaving
+    /// this vector with elements `.. at`. Both keep this vector’s
+    /// element width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > self.len()`.
+    pub fn split_off(&mut self, at: u64) -> Self {
+        assert!(at <= self.len(), "IntVector::split_off: out of bounds");
+
+        let mut tail = IntVector::with_capacity(self.element_bits,
+                                                self.len() - at);
+
+        // Copy whole blocks’ worth of bits at a time, then finish off
+        // any remaining elements one by one.
+        let start_bit = at * self.element_bits as u64;
+        let full_blocks = Block::div_nbits(self.bit_len() - start_bit);
+        let nbits = Block::nbits();
+        tail.base.extend_blocks(self.element_bits,
+                                (0 .. full_blocks).map(|i| {
+            self.get_bits(start_bit + Block::mul_nbits(i), nbits)
+        }));
+
+        for i in at + tail.len() .. self.len() {
+            tail.push(self.get(i));
+        }
+
+        self.truncate(at);
+        tail
+    }
+
+    /// Returns the index of the first element equal to `value`, if any.
+    pub fn position(&self, value: Block) -> Option<u64> {
+        if value > Block::low_mask(self.element_bits) { return None }
+
+        if !self.is_aligned() {
+            return self.iter().position(|element| element == value)
+                       .map(|index| index as u64);
+        }
+
+        // When the elements are aligned we can compare every lane of a
+        // block at once: XOR with `value` in each lane, and then look
+        // for an all-zero lane.
+        let k = self.element_bits;
+        let lanes = Block::nbits() / k;
+        let mut pattern = Block::zero();
+        let mut high = Block::zero();
+        for lane in 0 .. lanes {
+            pattern = pattern | value << (lane * k);
+            high = high | Block::nth_mask(lane * k + k - 1);
+        }
+
+        for i in 0 .. self.block_len() {
+            let x = self.get_block(i) ^ pattern;
+            // Each lane’s high bit is set in `y` iff the lane is nonzero.
+            let y = ((x & !high) + !high) | x;
+            let zero_lanes = !y & high;
+
+            if zero_lanes != Block::zero() {
+                let lane = zero_lanes.trailing_zeros() as usize / k;
+                let index = i as u64 * lanes as u64 + lane as u64;
+                // The match may be in the unused lanes of the last block.
+                return if index < self.len() {Some(index)} else {None};
+            }
+        }
+
+        None
+    }
+
+    /// True if some element equals `value`.
+    pub fn contains(&self, value: Block) -> bool {
+        self.position(value).is_some()
+    }
+
+    /// Adds corresponding elements, wrapping at the element width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vectors’ lengths or element widths differ.
+    pub fn add_wrapping(&self, other: &IntVector<Block>) -> Self {
+        assert_eq!(self.len(), other.len(),
+                   "IntVector::add_wrapping: lengths differ");
+        assert_eq!(self.element_bits, other.element_bits,
+                   "IntVector::add_wrapping: element widths differ");
+
+        let mask = Block::low_mask(self.element_bits);
+        let mut result = IntVector::with_capacity(self.element_bits,
+                                                  self.len());
+
+        for (a, b) in self.iter().zip(other.iter()) {
+            // When the elements fill the block, the sum can overflow
+            // the block too.
+            let sum = a.checked_add(&b)
+                       .unwrap_or_else(|| a - !b - Block::one());
+            result.push(sum & mask);
+        }
+
+        result
+    }
+
+    /// Adds corresponding elements into a vector one bit wider, so
+    /// that no sum overflows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vectors’ lengths or element widths differ, or if
+    /// the element width already matches the block size.
+    pub fn add_widening(&self, other: &IntVector<Block>) -> Self {
+        assert_eq!(self.len(), other.len(),
+                   "IntVector::add_widening: lengths differ");
+        assert_eq!(self.element_bits, other.element_bits,
+                   "IntVector::add_widening: element widths differ");
+        assert!(!self.is_block_sized(),
+                "IntVector::add_widening: no room to widen");
+
+        let mut result = IntVector::with_capacity(self.element_bits + 1,
+                                                  self.len());
+
+        for (a, b) in self.iter().zip(other.iter()) {
+            result.push(a + b);
+        }
+
+        result
+    }
+
+    /// Encodes each element to `sink` using `code`.
+    ///
+    /// Many codes can’t encode 0; wrap them in
+    /// [`Lift0`](../coding/struct.Lift0.html) if the vector may contain 0s.
+    pub fn encode_with<C, W>(&self, code: &C, sink: &mut W) -> Result<()>
+        where C: UniversalCode, W: BitWrite
+    {
+        for element in self {
+            match element.to_u64() {
+                Some(value) => try!(code.encode(sink, value)),
+                None => return too_many_bits("IntVector::encode_with"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes elements from `source` using `code` until it runs out,
+    /// collecting them into a vector of `element_bits`-bit elements.
+    ///
+    /// This is the inverse of [`encode_with`](#method.encode_with).
+    /// Returns an error if a decoded value doesn’t fit in
+    /// `element_bits` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `element_bits` is 0 or exceeds `Block::nbits()`.
+    pub fn decode_from<C, R>(code: &C, source: &mut R, element_bits: usize)
+                             -> Result<Self>
+        where C: UniversalCode, R: BitRead
+    {
+        let mut result = IntVector::new(element_bits);
+
+        while let Some(value) = try!(code.decode(source)) {
+            match cast::<u64, Block>(value) {
+                Some(element) if element <= Block::low_mask(element_bits) =>
+                    result.push(element),
+                _ => return too_many_bits("IntVector::decode_from"),
+            }
+        }
+
+        Ok(result)
+    }
+
     /// True if the element size matches the block size.
     #[inline]
     pub fn is_block_sized(&self) -> bool {
@@ -406,6 +997,34 @@ impl<'a, Block: BlockType + 'a> IntoIterator for &'a IntVector<Block> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<'de, Block> Deserialize<'de> for IntVector<Block>
+        where Block: BlockType + Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: Deserializer<'de> {
+
+        #[derive(Deserialize)]
+        #[serde(rename = "IntVector")]
+        struct Fields<Block> {
+            element_bits: usize,
+            base: VectorBase<Block>,
+        }
+
+        let fields = try!(Fields::deserialize(deserializer));
+
+        if fields.element_bits == 0
+            || fields.element_bits > Block::nbits()
+            || !fields.base.is_valid(fields.element_bits) {
+            return Err(de::Error::custom("IntVector: invalid data"));
+        }
+
+        Ok(IntVector {
+            element_bits: fields.element_bits,
+            base: fields.base,
+        })
+    }
+}
+
 impl<Block> fmt::Debug for IntVector<Block>
         where Block: BlockType + fmt::Debug {
 
@@ -433,8 +1052,13 @@ impl<A: BlockType> SpaceUsage for IntVector<A> {
 
 #[cfg(test)]
 mod test {
-    use int_vec::{IntVector, IntVec, IntVecMut};
+    use int_vec::{IntVector, IntVec, IntVecMut, SetError,
+                  ValueTooLarge};
     use bit_vec::*;
+    use coding::{UniversalCode, Lift0, GAMMA, DELTA};
+    use quickcheck::quickcheck;
+    use storage::BlockType;
+    use stream::BitBuffer;
 
     #[test]
     fn create_empty() {
@@ -442,6 +1066,38 @@ mod test {
         assert!(v.is_empty());
     }
 
+    #[test]
+    fn from_blocks() {
+        // Eight 8-bit elements, packed low byte first.
+        let blocks = vec![0x0403_0201u32, 0x0807_0605];
+        let v = IntVector::from_blocks(8, 8, blocks);
+        assert_eq!(8, v.len());
+        assert_eq!((1 .. 9).collect::<Vec<u32>>(),
+                   v.iter().collect::<Vec<_>>());
+
+        // Whole 32-bit elements are just the blocks.
+        let v = IntVector::from_blocks(32, 3, vec![7u32, 8, 9]);
+        assert_eq!(vec![7, 8, 9], v.iter().collect::<Vec<_>>());
+
+        // Extra blocks and bits past the end are discarded.
+        let mut padded = IntVector::<u32>::new(5);
+        for i in 0 .. 7 {
+            padded.push(i);
+        }
+        // The 35 bits of elements end 3 bits into the second block.
+        let blocks = vec![padded.get_block(0),
+                          padded.get_block(1) | !0 << 3,
+                          !0];
+        let v = IntVector::from_blocks(5, 7, blocks);
+        assert_eq!(padded, v);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_blocks_too_short() {
+        IntVector::from_blocks(5, 7, vec![0u32]);
+    }
+
     #[test]
     fn block_sized() {
         let mut v = IntVector::<u32>::with_fill(32, 10, 0);
@@ -528,6 +1184,29 @@ mod test {
         assert_eq!(9, v.get(19));
     }
 
+    #[test]
+    fn u128_blocks() {
+        let mut v = IntVector::<u128>::with_fill(100, 10, 0);
+        assert_eq!(8, v.block_len());
+
+        let big = (1u128 << 99) | 0xDEAD_BEEF;
+        v.set(0, big);
+        v.set(1, 5);
+        v.set(9, big >> 1);
+        assert_eq!(big, v.get(0));
+        assert_eq!(5, v.get(1));
+        assert_eq!(0, v.get(2));
+        assert_eq!(big >> 1, v.get(9));
+
+        v.push(1 << 64);
+        assert_eq!(Some(1 << 64), v.pop());
+        assert_eq!(Some(big), v.max_element());
+
+        let w = IntVector::<u128>::with_fill(128, 3, !0);
+        assert!(w.is_block_sized());
+        assert_eq!(!0, w.get(2));
+    }
+
     #[test]
     #[should_panic]
     fn unaligned_oob() {
@@ -642,4 +1321,798 @@ mod test {
         assert_eq!(Some(0), v.pop());
         assert_eq!(None, v.pop());
     }
+
+    #[test]
+    fn resize_with() {
+        let mut v = IntVector::<u32>::new(5);
+        v.push(7);
+
+        let mut calls = 0;
+        v.resize_with(6, || { calls += 1; calls });
+        assert_eq!(5, calls);
+        assert_eq!(vec![7, 1, 2, 3, 4, 5], v.iter().collect::<Vec<_>>());
+
+        v.resize_with(2, || panic!("resize_with: called when shrinking"));
+        assert_eq!(vec![7, 1], v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn gather() {
+        let mut v = IntVector::<u16>::new(2);
+        for &e in &[0, 1, 2, 1, 0, 3, 3] {
+            v.push(e);
+        }
+
+        let w = v.gather(&[7, 2, 4, 0]);
+        assert_eq!(3, w.element_bits());
+        assert_eq!(vec![7, 2, 4, 2, 7, 0, 0], w.iter().collect::<Vec<_>>());
+
+        let w = v.gather(&[8, 8, 8, 8]);
+        assert_eq!(4, w.element_bits());
+
+        let w = v.gather(&[0, 0, 0, 0]);
+        assert_eq!(1, w.element_bits());
+        assert_eq!(7, w.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn gather_oob() {
+        let mut v = IntVector::<u16>::new(2);
+        v.push(3);
+        v.gather(&[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn resize_with_overflow() {
+        let mut v = IntVector::<u32>::new(3);
+        v.resize_with(4, || 8);
+    }
+
+    #[test]
+    fn concat() {
+        let mut parts = Vec::new();
+        let mut expected = Vec::new();
+
+        for (n, &len) in [0, 3, 13, 4, 26, 1].iter().enumerate() {
+            let mut part = IntVector::<u16>::new(5);
+            for i in 0 .. len {
+                let value = (i * 7 + n as u16) % 32;
+                part.push(value);
+                expected.push(value);
+            }
+            parts.push(part);
+        }
+
+        let v = IntVector::concat(&parts);
+        assert_eq!(5, v.element_bits());
+        assert_eq!(expected, v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn concat_straddling() {
+        // 5-bit elements in 16-bit blocks: with a 16-element first part
+        // the seam is block aligned, and the second part’s copied blocks
+        // hold elements that straddle block boundaries.
+        let mut a = IntVector::<u16>::new(5);
+        let mut b = IntVector::<u16>::new(5);
+        let mut expected = Vec::new();
+        for i in 0 .. 16 {
+            a.push(i);
+            expected.push(i);
+        }
+        for i in 0 .. 20 {
+            b.push(31 - i);
+            expected.push(31 - i);
+        }
+
+        let v = IntVector::concat(&[a, b]);
+        assert_eq!(expected, v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn concat_block_sized() {
+        let a = IntVector::<u8>::with_fill(8, 3, 1);
+        let b = IntVector::<u8>::with_fill(8, 2, 2);
+
+        let v = IntVector::concat(&[a, b.clone(), b]);
+        assert_eq!(vec![1, 1, 1, 2, 2, 2, 2], v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn concat_width_mismatch() {
+        IntVector::<u8>::concat(&[IntVector::new(3), IntVector::new(4)]);
+    }
+
+    #[test]
+    fn append_aligned() {
+        let mut v = IntVector::<u8>::with_fill(4, 4, 9);
+        let mut w = IntVector::<u8>::new(4);
+        for i in 0 .. 5 {
+            w.push(i);
+        }
+
+        v.append(&w);
+        assert_eq!(9, v.len());
+        assert_eq!(vec![9, 9, 9, 9, 0, 1, 2, 3, 4],
+                   v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn append_unaligned() {
+        let mut v = IntVector::<u8>::with_fill(3, 5, 5);
+        let mut w = IntVector::<u8>::new(3);
+        for i in 0 .. 7 {
+            w.push(i);
+        }
+
+        v.append(&w);
+        assert_eq!(12, v.len());
+        assert_eq!(vec![5, 5, 5, 5, 5, 0, 1, 2, 3, 4, 5, 6],
+                   v.iter().collect::<Vec<_>>());
+
+        // With 8 elements the seam is on a block boundary, and an
+        // element of `w` straddles the blocks copied.
+        let mut v = IntVector::<u8>::with_fill(3, 8, 1);
+        v.append(&w);
+        assert_eq!(15, v.len());
+        assert_eq!(vec![1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 2, 3, 4, 5, 6],
+                   v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn append_width_mismatch() {
+        let mut v = IntVector::<u8>::new(3);
+        v.append(&IntVector::new(4));
+    }
+
+    #[test]
+    fn split_off_append() {
+        fn prop(element_bits: usize, values: Vec<u16>, at: usize) -> bool {
+            let element_bits = element_bits % 16 + 1;
+            let mask = u16::low_mask(element_bits);
+            let values: Vec<u16> = values.into_iter().map(|v| v & mask)
+                                         .collect();
+            let at = at % (values.len() + 1);
+
+            let mut original = IntVector::<u16>::new(element_bits);
+            original.extend_from_slice(&values);
+
+            let mut head = original.clone();
+            let tail = head.split_off(at as u64);
+
+            let mut expected_head = IntVector::new(element_bits);
+            expected_head.extend_from_slice(&values[.. at]);
+            let mut expected_tail = IntVector::new(element_bits);
+            expected_tail.extend_from_slice(&values[at ..]);
+
+            // Comparing with freshly built vectors also checks that
+            // the bits past the end are zero.
+            let split_ok = head == expected_head && tail == expected_tail
+                && tail.element_bits() == element_bits;
+
+            head.append(&tail);
+            split_ok && head == original
+        }
+
+        quickcheck(prop as fn(usize, Vec<u16>, usize) -> bool);
+    }
+
+    #[test]
+    fn split_off() {
+        let mut v = IntVector::<u8>::new(3);
+        for i in 0 .. 20 {
+            v.push(i % 8);
+        }
+
+        let tail = v.split_off(7);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 6], v.iter().collect::<Vec<_>>());
+        assert_eq!(vec![7, 0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3],
+                   tail.iter().collect::<Vec<_>>());
+
+        let mut v2 = v.clone();
+        assert!(v2.split_off(7).is_empty());
+        assert_eq!(v, v2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_oob() {
+        IntVector::<u8>::with_fill(3, 5, 0).split_off(6);
+    }
+
+    #[test]
+    fn retain() {
+        let mut v = IntVector::<u16>::new(5);
+        for i in 0 .. 20 {
+            v.push(i);
+        }
+
+        let mut evens = v.clone();
+        evens.retain(|e| e % 2 == 0);
+        assert_eq!(10, evens.len());
+        assert_eq!((0 .. 10).map(|i| 2 * i).collect::<Vec<_>>(),
+                   evens.iter().collect::<Vec<_>>());
+
+        let mut odds = v.clone();
+        odds.retain(|e| e % 2 == 1);
+        assert_eq!(10, odds.len());
+        assert_eq!((0 .. 10).map(|i| 2 * i + 1).collect::<Vec<_>>(),
+                   odds.iter().collect::<Vec<_>>());
+
+        v.retain(|_| false);
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn map_in_place() {
+        let mut v = IntVector::<u8>::new(6);
+        for i in 0 .. 32 {
+            v.push(i);
+        }
+
+        v.map_in_place(|e| 2 * e);
+        assert_eq!(6, v.element_bits());
+        assert_eq!(32, v.len());
+        assert_eq!((0 .. 32).map(|i| 2 * i).collect::<Vec<_>>(),
+                   v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn map_in_place_too_wide() {
+        let mut v = IntVector::<u8>::with_fill(6, 3, 32);
+        v.map_in_place(|e| 2 * e);
+    }
+
+    #[test]
+    fn add_wrapping() {
+        let mut v = IntVector::<u8>::new(3);
+        let mut w = IntVector::<u8>::new(3);
+        for &(a, b) in &[(1, 2), (5, 3), (7, 7), (0, 6)] {
+            v.push(a);
+            w.push(b);
+        }
+
+        let sum = v.add_wrapping(&w);
+        assert_eq!(3, sum.element_bits());
+        assert_eq!(vec![3, 0, 6, 6], sum.iter().collect::<Vec<_>>());
+
+        let v = IntVector::<u8>::with_fill(8, 2, 200);
+        let w = IntVector::<u8>::with_fill(8, 2, 100);
+        assert_eq!(vec![44, 44],
+                   v.add_wrapping(&w).iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn add_widening() {
+        let mut v = IntVector::<u8>::new(3);
+        let mut w = IntVector::<u8>::new(3);
+        for &(a, b) in &[(1, 2), (5, 3), (7, 7), (0, 6)] {
+            v.push(a);
+            w.push(b);
+        }
+
+        let sum = v.add_widening(&w);
+        assert_eq!(4, sum.element_bits());
+        assert_eq!(vec![3, 8, 14, 6], sum.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_length_mismatch() {
+        let v = IntVector::<u8>::with_fill(3, 2, 1);
+        let w = IntVector::<u8>::with_fill(3, 3, 1);
+        v.add_wrapping(&w);
+    }
+
+    fn position_prop(element_bits: usize, elements: Vec<u8>, value: u8)
+                     -> bool {
+        let element_bits = element_bits % 8 + 1;
+        let mask = u8::low_mask(element_bits);
+
+        let mut v = IntVector::<u8>::new(element_bits);
+        for &e in &elements {
+            v.push(e & mask);
+        }
+
+        let expected = v.iter().position(|e| e == value).map(|i| i as u64);
+        v.position(value) == expected
+            && v.contains(value) == expected.is_some()
+    }
+
+    #[test]
+    fn position_qc() {
+        quickcheck(position_prop as fn(usize, Vec<u8>, u8) -> bool);
+    }
+
+    #[test]
+    fn position() {
+        let mut v = IntVector::<u32>::new(4);
+        for &e in &[3, 9, 0, 9, 12] {
+            v.push(e);
+        }
+
+        assert_eq!(Some(0), v.position(3));
+        assert_eq!(Some(1), v.position(9));
+        assert_eq!(Some(2), v.position(0));
+        assert_eq!(None, v.position(5));
+        assert_eq!(None, v.position(16));
+        assert!(v.contains(12));
+        assert!(!v.contains(1));
+
+        // The unused lanes of the last block are 0, but aren’t elements.
+        let v = IntVector::<u32>::with_fill(4, 3, 7);
+        assert_eq!(None, v.position(0));
+
+        let mut v = IntVector::<u32>::new(5);
+        for &e in &[3, 9, 0, 9, 31, 31, 31, 2] {
+            v.push(e);
+        }
+        assert_eq!(Some(4), v.position(31));
+        assert_eq!(Some(7), v.position(2));
+        assert_eq!(None, v.position(1));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut v = IntVector::<u8>::new(3);
+        for i in 1 .. 4 {
+            v.push(i);
+        }
+
+        v.reverse();
+        assert_eq!(vec![3, 2, 1], v.iter().collect::<Vec<_>>());
+
+        let mut w = IntVector::<u16>::new(7);
+        for i in 0 .. 30 {
+            w.push(i * 4 % 128);
+        }
+
+        let original = w.clone();
+        w.reverse();
+        assert_eq!(original.iter().rev().collect::<Vec<_>>(),
+                   w.iter().collect::<Vec<_>>());
+        w.reverse();
+        assert_eq!(original, w);
+    }
+
+    #[test]
+    fn first_last() {
+        let mut v = IntVector::<u8>::new(5);
+        assert_eq!(None, v.first());
+        assert_eq!(None, v.last());
+
+        v.push(17);
+        assert_eq!(Some(17), v.first());
+        assert_eq!(Some(17), v.last());
+
+        v.push(4);
+        v.push(30);
+        assert_eq!(Some(17), v.first());
+        assert_eq!(Some(30), v.last());
+    }
+
+    #[test]
+    fn try_push() {
+        let mut v = IntVector::<u16>::new(5);
+        assert_eq!(Ok(()), v.try_push(0));
+        assert_eq!(Ok(()), v.try_push(u16::low_mask(5)));
+
+        let error = v.try_push(u16::low_mask(5) + 1).unwrap_err();
+        assert_eq!(ValueTooLarge { value: 32, element_bits: 5 }, error);
+        assert_eq!("value 32 does not fit in 5 bits", error.to_string());
+        assert!(v.try_push(!0).is_err());
+
+        assert_eq!(2, v.len());
+        assert_eq!(vec![0, 31], v.iter().collect::<Vec<_>>());
+
+        let mut w = IntVector::<u8>::new(8);
+        assert_eq!(Ok(()), w.try_push(255));
+    }
+
+    #[test]
+    fn swap_qc() {
+        // Each operation is a swap if `j` is `Some`, and a swap_remove
+        // otherwise; indices are taken modulo the length.
+        fn prop(values: Vec<u8>, ops: Vec<(u8, Option<u8>)>) -> bool {
+            let mut expected: Vec<u8> =
+                values.iter().map(|&v| v & 0x3F).collect();
+            let mut actual = IntVector::<u16>::new(6);
+            for &v in &expected {
+                actual.push(v as u16);
+            }
+
+            for (i, j) in ops {
+                if expected.is_empty() { break; }
+                let len = expected.len();
+                let i = i as usize % len;
+
+                match j {
+                    Some(j) => {
+                        let j = j as usize % len;
+                        expected.swap(i, j);
+                        actual.swap(i as u64, j as u64);
+                    }
+                    None => {
+                        let removed = expected.swap_remove(i);
+                        if actual.swap_remove(i as u64) != removed as u16 {
+                            return false;
+                        }
+                    }
+                }
+            }
+
+            actual.iter().eq(expected.into_iter().map(|v| v as u16))
+        }
+
+        quickcheck(prop as fn(Vec<u8>, Vec<(u8, Option<u8>)>) -> bool);
+    }
+
+    #[test]
+    fn swap_remove() {
+        let mut v = IntVector::<u32>::new(5);
+        v.extend_from_slice(&[1, 2, 3, 31]);
+
+        v.swap(0, 3);
+        assert_eq!(vec![31, 2, 3, 1], v.iter().collect::<Vec<_>>());
+
+        assert_eq!(2, v.swap_remove(1));
+        assert_eq!(vec![31, 1, 3], v.iter().collect::<Vec<_>>());
+        assert_eq!(3, v.swap_remove(2));
+        assert_eq!(vec![31, 1], v.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_oob() {
+        let mut v = IntVector::<u32>::with_fill(5, 4, 0);
+        v.swap(1, 4);
+    }
+
+    #[test]
+    fn get_set_checked() {
+        let mut v = IntVector::<u32>::with_fill(5, 10, 0);
+        for i in 0 .. 10 {
+            assert_eq!(Ok(()), v.set_checked(i, i as u32 * 3));
+        }
+
+        for i in 0 .. 10 {
+            assert_eq!(Some(i as u32 * 3), v.get_checked(i));
+        }
+        assert_eq!(None, v.get_checked(10));
+        assert_eq!(None, v.get_checked(u64::MAX));
+
+        let too_large = |value| {
+            Err(SetError::TooLarge(ValueTooLarge { value, element_bits: 5 }))
+        };
+
+        assert_eq!(Err(SetError::OutOfBounds { index: 10, len: 10 }),
+                   v.set_checked(10, 7));
+        assert_eq!(Err(SetError::OutOfBounds { index: 10, len: 10 }),
+                   v.set_checked(10, 32));
+        assert_eq!(too_large(32), v.set_checked(9, 32));
+        assert_eq!(too_large(32), v.set_checked(0, 32));
+        assert_eq!(Some(27), v.get_checked(9));
+        assert_eq!(Ok(()), v.set_checked(9, 31));
+        assert_eq!(Some(31), v.get_checked(9));
+        assert_eq!(10, v.len());
+
+        let mut w = IntVector::<u8>::new(8);
+        assert_eq!(None, w.get_checked(0));
+        assert_eq!(Err(SetError::OutOfBounds { index: 0, len: 0 }),
+                   w.set_checked(0, 1));
+        w.push(0);
+        assert_eq!(Ok(()), w.set_checked(0, 255));
+    }
+
+    #[test]
+    fn reserve_then_push() {
+        let mut v = IntVector::<u16>::new(5);
+        v.push(1);
+        v.reserve(100);
+        let capacity = v.block_capacity();
+        assert!(v.capacity() >= 101);
+
+        for i in 0 .. 100 {
+            v.push(i % 32);
+        }
+        assert_eq!(capacity, v.block_capacity());
+
+        let mut v = IntVector::<u16>::new(5);
+        v.reserve_exact(100);
+        let capacity = v.block_capacity();
+        for i in 0 .. 100 {
+            v.push(i % 32);
+        }
+        assert_eq!(capacity, v.block_capacity());
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut v = IntVector::<u16>::with_capacity(5, 1000);
+        for i in 0 .. 30 {
+            v.push(i);
+        }
+
+        v.shrink_to_fit();
+        assert_eq!(v.block_len(), v.block_capacity());
+        assert_eq!((0 .. 30).collect::<Vec<_>>(),
+                   v.iter().collect::<Vec<_>>());
+
+        v.clear();
+        v.shrink_to_fit();
+        assert_eq!(0, v.block_capacity());
+    }
+
+    fn encode_decode<C: UniversalCode>(code: &C) {
+        let mut v = IntVector::<u32>::new(11);
+        for i in 0 .. 200 {
+            v.push(i * 37 % 2048);
+        }
+
+        let mut buffer: BitBuffer<BitVector> = BitBuffer::new();
+        v.encode_with(code, &mut buffer).unwrap();
+
+        let mut buffer = BitBuffer::from(buffer.into_inner());
+        let w = IntVector::decode_from(code, &mut buffer, 11).unwrap();
+        assert_eq!(v, w);
+    }
+
+    #[test]
+    fn encode_decode_gamma() {
+        encode_decode(&Lift0(GAMMA));
+    }
+
+    #[test]
+    fn encode_decode_delta() {
+        encode_decode(&Lift0(DELTA));
+    }
+
+    #[test]
+    fn decode_too_wide() {
+        let mut v = IntVector::<u32>::new(8);
+        v.push(200);
+
+        let mut buffer: BitBuffer<BitVector> = BitBuffer::new();
+        v.encode_with(&GAMMA, &mut buffer).unwrap();
+
+        let mut buffer = BitBuffer::from(buffer.into_inner());
+        assert!(IntVector::<u32>::decode_from(&GAMMA, &mut buffer, 7).is_err());
+    }
+
+    #[test]
+    fn iter_range() {
+        let mut v = IntVector::<u16>::new(5);
+        for i in 0 .. 40 {
+            v.push(i * 3 % 32);
+        }
+
+        for &(start, len) in &[(0, 0), (0, 40), (3, 10), (39, 1), (40, 0)] {
+            assert_eq!((start .. start + len).map(|i| v.get(i))
+                                             .collect::<Vec<_>>(),
+                       v.iter_range(start, len).collect::<Vec<_>>());
+        }
+
+        assert_eq!(10, v.iter_range(3, 10).len());
+        assert_eq!(Some(v.get(12)), v.iter_range(3, 10).last());
+    }
+
+    #[test]
+    fn reinterpret_iter() {
+        let mut v = IntVector::<u32>::new(8);
+        v.extend_from_slice(&[0x21, 0x43, 0xA5]);
+
+        let nibbles: Vec<u32> = v.reinterpret_iter(4).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 0xA], nibbles);
+
+        let wide: Vec<u32> = v.reinterpret_iter(16).collect();
+        assert_eq!(vec![0x4321, 0xA5], wide);
+
+        let bits: Vec<u32> = v.reinterpret_iter(1).take(8).collect();
+        assert_eq!(vec![1, 0, 0, 0, 0, 1, 0, 0], bits);
+
+        let mut w = IntVector::<u8>::new(5);
+        w.push(0b11111);
+        w.push(0b00001);
+        let threes: Vec<u8> = w.reinterpret_iter(3).collect();
+        assert_eq!(vec![0b111, 0b111, 0b000, 0b0], threes);
+
+        assert_eq!(0, IntVector::<u8>::new(3).reinterpret_iter(2).count());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reinterpret_iter_too_wide() {
+        let _ = IntVector::<u8>::new(3).reinterpret_iter(9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn iter_range_oob() {
+        let v = IntVector::<u16>::with_fill(5, 10, 1);
+        v.iter_range(5, 6);
+    }
+
+    #[test]
+    fn repack() {
+        let mut v = IntVector::<u16>::new(5);
+        for i in 0 .. 30 {
+            v.push(i % 32);
+        }
+
+        let wide = v.repack(9);
+        assert_eq!(9, wide.element_bits());
+        assert_eq!(v.iter().collect::<Vec<_>>(),
+                   wide.iter().collect::<Vec<_>>());
+
+        let narrow = wide.repack(5);
+        assert_eq!(v, narrow);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repack_too_narrow() {
+        let v = IntVector::<u16>::with_fill(9, 3, 300);
+        v.repack(5);
+    }
+
+    #[test]
+    fn compress_width() {
+        let mut v = IntVector::<u32>::new(20);
+        for &e in &[3, 17, 0, 8, 16] {
+            v.push(e);
+        }
+
+        let w = v.compress_width();
+        assert_eq!(5, w.element_bits());
+        assert_eq!(v.iter().collect::<Vec<_>>(),
+                   w.iter().collect::<Vec<_>>());
+
+        v.set(1, 15);
+        v.set(4, 9);
+        assert_eq!(4, v.compress_width().element_bits());
+
+        let zeros = IntVector::<u32>::with_fill(20, 10, 0);
+        assert_eq!(1, zeros.compress_width().element_bits());
+
+        let empty = IntVector::<u32>::new(20);
+        assert_eq!(1, empty.compress_width().element_bits());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use serde_json;
+
+        let mut v = IntVector::<u32>::new(13);
+        for i in 0 .. 50 {
+            v.push(i * 97 % 8000);
+        }
+
+        let json = serde_json::to_string(&v).unwrap();
+        let copy: IntVector<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(v, copy);
+
+        assert!(serde_json::from_str::<IntVector<u32>>(
+            r#"{"element_bits":33,"base":{"len":0,"vec":[]}}"#).is_err());
+        assert!(serde_json::from_str::<IntVector<u32>>(
+            r#"{"element_bits":13,"base":{"len":3,"vec":[]}}"#).is_err());
+    }
+
+    #[test]
+    fn sort() {
+        fn prop(values: Vec<u16>, element_bits: u8) -> bool {
+            let element_bits = element_bits as usize % 16 + 1;
+            let mask = u16::low_mask(element_bits);
+
+            let mut v = IntVector::<u32>::new(element_bits);
+            let mut expected: Vec<u32> = Vec::new();
+            for &value in &values {
+                v.push((value & mask) as u32);
+                expected.push((value & mask) as u32);
+            }
+            expected.sort();
+
+            let original: Vec<u32> = v.iter().collect();
+            let was_sorted = v.is_sorted();
+            v.sort();
+
+            was_sorted == (original == expected)
+                && v.is_sorted()
+                && v.element_bits() == element_bits
+                && v.iter().collect::<Vec<_>>() == expected
+        }
+
+        quickcheck(prop as fn(Vec<u16>, u8) -> bool);
+
+        let mut v = IntVector::<u64>::new(7);
+        assert!(v.is_sorted());
+        v.push(3);
+        v.push(3);
+        assert!(v.is_sorted());
+        v.push(2);
+        assert!(!v.is_sorted());
+    }
+
+    #[test]
+    fn min_max_sum() {
+        let mut v = IntVector::<u8>::new(7);
+        assert_eq!(None, v.min_element());
+        assert_eq!(None, v.max_element());
+        assert_eq!(0, v.sum_u64());
+
+        for &e in &[100, 7, 127, 0, 64, 99] {
+            v.push(e);
+        }
+
+        assert_eq!(Some(0), v.min_element());
+        assert_eq!(Some(127), v.max_element());
+        // More than fits in a `u8`.
+        assert_eq!(397, v.sum_u64());
+
+        let w = IntVector::<u16>::with_fill(13, 1000, 8191);
+        assert_eq!(Some(8191), w.min_element());
+        assert_eq!(Some(8191), w.max_element());
+        assert_eq!(8_191_000, w.sum_u64());
+    }
+
+    #[test]
+    fn extend_from_slice() {
+        let values: Vec<u16> = (0 .. 300u16).map(|i| (i * 37) ^ (i >> 2))
+                                            .collect();
+
+        for element_bits in 1 .. 17 {
+            let mask = u16::low_mask(element_bits);
+            let values: Vec<u16> = values.iter().map(|&v| v & mask)
+                                                .collect();
+
+            // Starting both aligned and unaligned.
+            for &start in &[0, 3, 16] {
+                let mut expected = IntVector::<u16>::new(element_bits);
+                let mut actual = IntVector::<u16>::new(element_bits);
+                for &value in &values[.. start] {
+                    expected.push(value);
+                    actual.push(value);
+                }
+
+                for &value in &values {
+                    expected.push(value);
+                }
+                actual.extend_from_slice(&values);
+
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn extend_from_slice_too_large() {
+        let mut v = IntVector::<u16>::new(4);
+        v.extend_from_slice(&[1, 2, 3, 16]);
+    }
+
+    #[test]
+    fn extend_from_slice_too_large_unchanged() {
+        use std::panic::{self, AssertUnwindSafe};
+
+        // The bad value is in the second whole block of the batch, so
+        // the first would be packed before reaching it.
+        let mut v = IntVector::<u16>::with_fill(4, 4, 7);
+        let before = v.clone();
+        let values = [1, 2, 3, 4, 5, 6, 7, 16, 9];
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.extend_from_slice(&values);
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(before, v);
+        assert_eq!(before.block_len(), v.block_len());
+        assert!(v.base.is_valid(4));
+    }
 }
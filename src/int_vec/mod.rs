@@ -5,3 +5,21 @@ pub use self::int_vector::*;
 
 mod traits;
 pub use self::traits::*;
+
+mod error;
+pub use self::error::*;
+
+mod value_rank_select;
+pub use self::value_rank_select::*;
+
+mod int_set;
+pub use self::int_set::*;
+
+mod coded_int_vec;
+pub use self::coded_int_vec::*;
+
+mod fenwick;
+pub use self::fenwick::*;
+
+mod pfor;
+pub use self::pfor::*;
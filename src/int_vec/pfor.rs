@@ -0,0 +1,357 @@
+use bit_vec::{BitSlice, BitVector};
+use coding::{UniversalCode, DELTA, GAMMA};
+use int_vec::IntVector;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+use stream::{BitBuffer, BitRead};
+
+/// A vector of `u64`s compressed with patched frame-of-reference
+/// (PForDelta-style) coding.
+///
+/// The elements are split into blocks of `PFOR_BLOCK_LEN`. Each block
+/// stores its minimum as a base, and every element as its difference
+/// from the base, packed at a width chosen per block. Differences too
+/// wide for the block’s width are *exceptions*: their low bits are
+/// packed as usual, and their high bits are kept in a separate list,
+/// along with their offsets in the block, encoded with universal codes.
+///
+/// The width is chosen to minimize the block’s size, so a few outliers
+/// don’t force every element of the block to be wide.
+#[derive(Clone, Debug)]
+pub struct PForVec {
+    blocks: Vec<PForBlock>,
+    packed: IntVector<u64>,
+    exceptions: BitVector<u64>,
+    len: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PForBlock {
+    base: u64,
+    width: usize,
+    // Bit offset of the block’s packed elements.
+    packed: u64,
+    // Bit offset and number of the block’s exceptions. Each exception
+    // is the gap from the previous exception’s offset (or −1) in
+    // gamma, followed by its high bits in delta.
+    exceptions: u64,
+    n_exceptions: usize,
+}
+
+impl_stack_only_space_usage!(PForBlock);
+
+/// The number of elements in each block of a `PForVec`.
+pub const PFOR_BLOCK_LEN: usize = 128;
+
+impl PForVec {
+    /// Compresses a slice of values.
+    pub fn from_slice(values: &[u64]) -> Self {
+        let mut blocks = Vec::with_capacity(
+            values.len().ceil_div(PFOR_BLOCK_LEN));
+        let mut lows = Vec::with_capacity(values.len());
+        let mut buffer: BitBuffer<BitVector<u64>> = BitBuffer::new();
+        let mut packed_bits = 0;
+
+        for chunk in values.chunks(PFOR_BLOCK_LEN) {
+            let base = *chunk.iter().min().unwrap();
+            let diffs: Vec<u64> = chunk.iter().map(|&v| v - base).collect();
+            let width = best_width(&diffs);
+
+            blocks.push(PForBlock {
+                base,
+                width,
+                packed: packed_bits,
+                exceptions: buffer.position(),
+                n_exceptions: 0,
+            });
+
+            let mut last = None;
+            let mut n_exceptions = 0;
+            for (i, &diff) in diffs.iter().enumerate() {
+                let high = high_bits(diff, width);
+                if high != 0 {
+                    GAMMA.encode(&mut buffer, gap(last, i))
+                         .expect("PForVec::from_slice: could not encode");
+                    DELTA.encode(&mut buffer, high)
+                         .expect("PForVec::from_slice: could not encode");
+                    last = Some(i);
+                    n_exceptions += 1;
+                }
+                lows.push(diff & u64::low_mask(width));
+            }
+
+            blocks.last_mut().unwrap().n_exceptions = n_exceptions;
+            packed_bits += (width * chunk.len()) as u64;
+        }
+
+        let mut packed = IntVector::with_fill(1, packed_bits, 0);
+        for (block, chunk) in blocks.iter().zip(lows.chunks(PFOR_BLOCK_LEN)) {
+            if block.width == 0 { continue }
+            for (i, &low) in chunk.iter().enumerate() {
+                packed.set_random(block.packed, block.width, i as u64, low);
+            }
+        }
+
+        let mut exceptions = buffer.into_inner();
+        exceptions.shrink_to_fit();
+
+        PForVec {
+            blocks,
+            packed,
+            exceptions,
+            len: values.len() as u64,
+        }
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the vector empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: u64) -> u64 {
+        assert!(index < self.len, "PForVec::get: out of bounds");
+
+        let block = &self.blocks[(index / PFOR_BLOCK_LEN as u64) as usize];
+        let within = (index % PFOR_BLOCK_LEN as u64) as usize;
+        let mut result = self.get_low(block, within);
+
+        let mut buffer = self.exception_buffer(block);
+        let mut last = None;
+        for _ in 0 .. block.n_exceptions {
+            let (offset, high) = decode_exception(&mut buffer, last);
+            if offset == within {
+                result |= high << block.width;
+                break;
+            } else if offset > within {
+                break;
+            }
+            last = Some(offset);
+        }
+
+        block.base + result
+    }
+
+    /// Gets an iterator over the elements.
+    pub fn iter(&self) -> PForIter<'_> {
+        PForIter {
+            vec: self,
+            buffer: Vec::with_capacity(PFOR_BLOCK_LEN),
+            index: 0,
+        }
+    }
+
+    fn get_low(&self, block: &PForBlock, within: usize) -> u64 {
+        if block.width == 0 {
+            0
+        } else {
+            self.packed.get_random(block.packed, block.width, within as u64)
+        }
+    }
+
+    fn exception_buffer(&self, block: &PForBlock)
+                        -> BitBuffer<BitSlice<'_, BitVector<u64>>> {
+        BitBuffer::from(BitSlice::new(&self.exceptions, block.exceptions ..))
+    }
+
+    // Decodes the `n`th block into `out`, replacing its contents.
+    fn decode_block(&self, n: usize, out: &mut Vec<u64>) {
+        let block = &self.blocks[n];
+        let start = (n * PFOR_BLOCK_LEN) as u64;
+        let block_len = (self.len - start).min(PFOR_BLOCK_LEN as u64);
+
+        out.clear();
+        out.extend((0 .. block_len as usize)
+                       .map(|i| self.get_low(block, i)));
+
+        let mut buffer = self.exception_buffer(block);
+        let mut last = None;
+        for _ in 0 .. block.n_exceptions {
+            let (offset, high) = decode_exception(&mut buffer, last);
+            out[offset] |= high << block.width;
+            last = Some(offset);
+        }
+
+        for value in out.iter_mut() {
+            *value += block.base;
+        }
+    }
+}
+
+// The high bits of `diff` that don’t fit in `width`.
+fn high_bits(diff: u64, width: usize) -> u64 {
+    if width == 64 { 0 } else { diff >> width }
+}
+
+// The gamma-coded gap from the previous exception to offset `i`.
+fn gap(last: Option<usize>, i: usize) -> u64 {
+    match last {
+        Some(last) => (i - last) as u64,
+        None => i as u64 + 1,
+    }
+}
+
+fn decode_exception<R>(buffer: &mut R, last: Option<usize>) -> (usize, u64)
+    where R: BitRead
+{
+    let gap = GAMMA.decode(buffer)
+                   .expect("PForVec: could not decode")
+                   .expect("PForVec: unexpected end of exceptions");
+    let high = DELTA.decode(buffer)
+                    .expect("PForVec: could not decode")
+                    .expect("PForVec: unexpected end of exceptions");
+    let offset = match last {
+        Some(last) => last + gap as usize,
+        None => gap as usize - 1,
+    };
+    (offset, high)
+}
+
+fn gamma_bits(n: u64) -> usize {
+    2 * n.floor_lg() + 1
+}
+
+fn delta_bits(n: u64) -> usize {
+    let lg = n.floor_lg();
+    lg + gamma_bits(lg as u64 + 1)
+}
+
+// Chooses the width that minimizes the encoded size of `diffs`.
+fn best_width(diffs: &[u64]) -> usize {
+    let mut best = (usize::MAX, 0);
+
+    for width in 0 ..= 64 {
+        let mut cost = width * diffs.len();
+        let mut last = None;
+        for (i, &diff) in diffs.iter().enumerate() {
+            let high = high_bits(diff, width);
+            if high != 0 {
+                cost += gamma_bits(gap(last, i)) + delta_bits(high);
+                last = Some(i);
+            }
+        }
+
+        if cost < best.0 {
+            best = (cost, width);
+        }
+    }
+
+    best.1
+}
+
+impl SpaceUsage for PForVec {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.blocks.heap_bytes() + self.packed.heap_bytes()
+            + self.exceptions.heap_bytes()
+    }
+}
+
+impl<'a> IntoIterator for &'a PForVec {
+    type Item = u64;
+    type IntoIter = PForIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the elements of a `PForVec`.
+pub struct PForIter<'a> {
+    vec: &'a PForVec,
+    buffer: Vec<u64>,
+    index: u64,
+}
+
+impl<'a> Iterator for PForIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index >= self.vec.len { return None }
+
+        let within = (self.index % PFOR_BLOCK_LEN as u64) as usize;
+        if within == 0 {
+            let block = (self.index / PFOR_BLOCK_LEN as u64) as usize;
+            self.vec.decode_block(block, &mut self.buffer);
+        }
+
+        self.index += 1;
+        Some(self.buffer[within])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.vec.len - self.index) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for PForIter<'a> {}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::quickcheck;
+
+    use space_usage::SpaceUsage;
+    use super::*;
+
+    fn matches_vec(values: &[u64]) -> bool {
+        let pfor = PForVec::from_slice(values);
+        pfor.len() == values.len() as u64
+            && pfor.iter().eq(values.iter().cloned())
+            && (0 .. pfor.len()).all(|i| pfor.get(i) == values[i as usize])
+    }
+
+    #[test]
+    fn qc_matches_vec() {
+        fn prop(values: Vec<u64>) -> bool {
+            matches_vec(&values)
+        }
+
+        fn prop_small(values: Vec<u8>) -> bool {
+            let values: Vec<u64> = values.into_iter().map(u64::from)
+                                         .collect();
+            matches_vec(&values)
+        }
+
+        quickcheck(prop as fn(Vec<u64>) -> bool);
+        quickcheck(prop_small as fn(Vec<u8>) -> bool);
+    }
+
+    #[test]
+    fn empty() {
+        let pfor = PForVec::from_slice(&[]);
+        assert!(pfor.is_empty());
+        assert_eq!(None, pfor.iter().next());
+    }
+
+    #[test]
+    fn extremes() {
+        assert!(matches_vec(&[0, u64::MAX, 7, u64::MAX - 1]));
+        assert!(matches_vec(&[u64::MAX; 300]));
+        assert!(matches_vec(&[5; 129]));
+    }
+
+    #[test]
+    fn skewed() {
+        // Mostly small values, with a large outlier every 50 elements.
+        let values: Vec<u64> = (0 .. 10_000u64)
+            .map(|i| if i % 50 == 0 { 1 << 40 | i } else { 100 + i % 13 })
+            .collect();
+
+        let pfor = PForVec::from_slice(&values);
+        assert!(matches_vec(&values));
+
+        // Packing everything at 41 bits would take over 50 KB.
+        assert!(pfor.total_bytes() < 10_000);
+    }
+}
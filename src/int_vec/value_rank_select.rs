@@ -0,0 +1,186 @@
+use bit_vec::{BitVecPush, BitVector};
+use int_vec::{IntVec, IntVector};
+use rank::{BitRankSupport, RankSupport, Rank9};
+use select::{BinSearchSelect, Select1Support, SelectSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// Rank and select over the elements of an `IntVector`, indexed for
+/// one particular value.
+///
+/// Construction marks the positions holding the indexed value in a bit
+/// vector with a `Rank9` directory, so queries for that value take
+/// constant time (rank) or logarithmic time (select). Queries for other
+/// values fall back to scanning the elements, except that values too
+/// wide for the elements always rank 0 and select `None`.
+#[derive(Clone, Debug)]
+pub struct ValueRankSelect<Block: BlockType = usize> {
+    elements: IntVector<Block>,
+    value: Block,
+    index: BinSearchSelect<Rank9<BitVector<u64>>>,
+}
+
+impl<Block: BlockType> ValueRankSelect<Block> {
+    /// Indexes the positions of `value` in `elements`.
+    pub fn new(elements: IntVector<Block>, value: Block) -> Self {
+        let mut bits = BitVector::with_capacity(elements.len());
+        for element in &elements {
+            bits.push_bit(element == value);
+        }
+
+        ValueRankSelect {
+            elements,
+            value,
+            index: BinSearchSelect::new(Rank9::new(bits)),
+        }
+    }
+
+    /// The value whose positions are indexed.
+    pub fn value(&self) -> Block {
+        self.value
+    }
+
+    /// Borrows a reference to the underlying elements.
+    pub fn inner(&self) -> &IntVector<Block> {
+        &self.elements
+    }
+
+    /// Returns the underlying elements.
+    pub fn into_inner(self) -> IntVector<Block> {
+        self.elements
+    }
+
+    fn in_range(&self, value: Block) -> bool {
+        value <= Block::low_mask(self.elements.element_bits())
+    }
+}
+
+impl<Block: BlockType> RankSupport for ValueRankSelect<Block> {
+    type Over = Block;
+
+    fn rank(&self, position: u64, value: Block) -> u64 {
+        assert!(position < self.limit(),
+                "ValueRankSelect::rank: out of bounds");
+
+        if value == self.value {
+            self.index.rank1(position)
+        } else if !self.in_range(value) {
+            0
+        } else {
+            (0 .. position + 1)
+                .filter(|&i| self.elements.get(i) == value)
+                .count() as u64
+        }
+    }
+
+    fn limit(&self) -> u64 {
+        self.elements.len()
+    }
+}
+
+impl<Block: BlockType> SelectSupport for ValueRankSelect<Block> {
+    type Over = Block;
+
+    fn select(&self, index: u64, value: Block) -> Option<u64> {
+        if value == self.value {
+            self.index.select1(index)
+        } else if !self.in_range(value) {
+            None
+        } else {
+            (0 .. self.limit())
+                .filter(|&i| self.elements.get(i) == value)
+                .nth(index as usize)
+        }
+    }
+}
+
+impl<Block: BlockType> SpaceUsage for ValueRankSelect<Block> {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.elements.heap_bytes() + self.index.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::quickcheck;
+
+    use int_vec::IntVector;
+    use rank::RankSupport;
+    use select::SelectSupport;
+    use super::*;
+
+    fn naive_rank(elements: &[u32], position: u64, value: u32) -> u64 {
+        elements[.. position as usize + 1].iter()
+            .filter(|&&e| e == value)
+            .count() as u64
+    }
+
+    fn naive_select(elements: &[u32], index: u64, value: u32) -> Option<u64> {
+        elements.iter()
+            .enumerate()
+            .filter(|&(_, &e)| e == value)
+            .map(|(i, _)| i as u64)
+            .nth(index as usize)
+    }
+
+    fn rank_select_prop(elements: Vec<u32>, value: u32) -> bool {
+        let elements: Vec<u32> = elements.iter().map(|&e| e % 8).collect();
+        let value = value % 10;
+
+        let mut v = IntVector::<u32>::new(3);
+        for &e in &elements {
+            v.push(e);
+        }
+
+        let rs = ValueRankSelect::new(v, value);
+
+        (0 .. 10).all(|query| {
+            (0 .. elements.len() as u64).all(|i| {
+                rs.rank(i, query) == naive_rank(&elements, i, query)
+                    && rs.select(i, query) == naive_select(&elements, i, query)
+            })
+        })
+    }
+
+    #[test]
+    fn rank_select_qc() {
+        quickcheck(rank_select_prop as fn(Vec<u32>, u32) -> bool);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let v = IntVector::<u32>::with_fill(3, 20, 7);
+        let rs = ValueRankSelect::new(v, 15);
+
+        assert_eq!(0, rs.rank(19, 15));
+        assert_eq!(None, rs.select(0, 15));
+        assert_eq!(0, rs.rank(19, 8));
+        assert_eq!(None, rs.select(0, 8));
+        assert_eq!(20, rs.rank(19, 7));
+        assert_eq!(Some(19), rs.select(19, 7));
+    }
+
+    #[test]
+    fn space() {
+        let mut v = IntVector::<u32>::new(4);
+        for i in 0 .. 1000 {
+            v.push(i % 16);
+        }
+
+        let heap = v.heap_bytes();
+        let rs = ValueRankSelect::new(v, 3);
+        assert!(rs.heap_bytes() > heap);
+        assert_eq!(63, rs.rank(999, 3));
+        assert_eq!(Some(3), rs.select(0, 3));
+    }
+
+    #[test]
+    fn clone() {
+        let v = IntVector::<u32>::with_fill(3, 20, 5);
+        let rs = ValueRankSelect::new(v, 5).clone();
+        assert_eq!(20, rs.rank(19, 5));
+        assert!(format!("{:?}", rs).starts_with("ValueRankSelect"));
+    }
+}
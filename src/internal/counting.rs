@@ -0,0 +1,43 @@
+use std::io::Result;
+
+use stream::{BitRead, BitWrite};
+
+/// Passes reads through to `source`, counting the bits.
+pub struct CountingReader<'a, R: 'a> {
+    pub source: &'a mut R,
+    pub count: usize,
+}
+
+impl<'a, R: BitRead> CountingReader<'a, R> {
+    pub fn new(source: &'a mut R) -> Self {
+        CountingReader { source, count: 0 }
+    }
+}
+
+impl<'a, R: BitRead> BitRead for CountingReader<'a, R> {
+    fn read_bit(&mut self) -> Result<Option<bool>> {
+        let result = try!(self.source.read_bit());
+        if result.is_some() { self.count += 1; }
+        Ok(result)
+    }
+}
+
+/// Passes writes through to `sink`, counting the bits.
+pub struct CountingWriter<'a, W: 'a> {
+    pub sink: &'a mut W,
+    pub count: usize,
+}
+
+impl<'a, W: BitWrite> CountingWriter<'a, W> {
+    pub fn new(sink: &'a mut W) -> Self {
+        CountingWriter { sink, count: 0 }
+    }
+}
+
+impl<'a, W: BitWrite> BitWrite for CountingWriter<'a, W> {
+    fn write_bit(&mut self, value: bool) -> Result<()> {
+        try!(self.sink.write_bit(value));
+        self.count += 1;
+        Ok(())
+    }
+}
@@ -10,3 +10,8 @@ pub fn too_many_bits<A>(who: &str) -> Result<A> {
     Err(Error::new(ErrorKind::InvalidData,
                    format!("{}: value too big for type", who)))
 }
+
+pub fn invalid_code<A>(who: &str) -> Result<A> {
+    Err(Error::new(ErrorKind::InvalidData,
+                   format!("{}: could not decode: invalid code", who)))
+}
@@ -1,3 +1,4 @@
+pub mod counting;
 pub mod errors;
 pub mod search;
 pub mod vector_base;
@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 
+use std::mem;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 #[cfg(target_pointer_width = "32")]
 use num_traits::ToPrimitive;
 
@@ -22,6 +26,7 @@ use storage::BlockType;
 /// it is assumed to have already been checked, so the client must ensure that it
 /// doesn’t pass bogus `element_bits` values.
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VectorBase<Block> {
     len: u64,
     vec: Vec<Block>,
@@ -51,6 +56,29 @@ impl<Block: BlockType> VectorBase<Block> {
         self.clear_extra_bits(element_bits);
     }
 
+    // Checks both invariants, for data that didn’t come from our own
+    // methods (such as by deserialization).
+    pub fn is_valid(&self, element_bits: usize) -> bool {
+        let bit_len = match self.len.checked_mul(element_bits as u64) {
+            Some(bit_len) => bit_len,
+            None => return false,
+        };
+
+        let partial = Block::mod_nbits(bit_len) > 0;
+        if self.vec.len() as u64 != Block::div_nbits(bit_len) as u64
+                                    + partial as u64 {
+            return false;
+        }
+
+        match self.vec.last() {
+            Some(&block) => {
+                let mask = Block::low_mask(Block::last_block_bits(bit_len));
+                block & !mask == Block::zero()
+            }
+            None => true,
+        }
+    }
+
     #[inline]
     pub fn new() -> Self {
         Self::block_with_capacity(0)
@@ -100,6 +128,20 @@ impl<Block: BlockType> VectorBase<Block> {
         result
     }
 
+    #[inline]
+    pub fn from_blocks(element_bits: usize, len: u64, mut vec: Vec<Block>)
+                       -> Self {
+        let block_len = len_to_block_len::<Block>(element_bits, len)
+                            .expect("VectorBase::from_blocks: overflow");
+        assert!(vec.len() >= block_len,
+                "VectorBase::from_blocks: not enough blocks");
+
+        vec.truncate(block_len);
+        let mut result = VectorBase { len, vec };
+        result.clear_extra_bits(element_bits);
+        result
+    }
+
     #[inline]
     pub fn get_block(&self, block_index: usize) -> Block {
         self.vec[block_index]
@@ -152,6 +194,14 @@ impl<Block: BlockType> VectorBase<Block> {
         self.set_len_from_blocks(element_bits);
     }
 
+    #[inline]
+    pub fn extend_blocks<I>(&mut self, element_bits: usize, blocks: I)
+        where I: IntoIterator<Item = Block>
+    {
+        self.vec.extend(blocks);
+        self.set_len_from_blocks(element_bits);
+    }
+
     #[inline]
     pub fn pop_block(&mut self, element_bits: usize) -> Option<Block> {
         let result = self.vec.pop();
@@ -265,7 +315,12 @@ impl<Block: BlockType> VectorBase<Block> {
 
     #[inline]
     pub fn shrink_to_fit(&mut self) {
-        self.vec.shrink_to_fit()
+        // `Vec::shrink_to_fit` may leave extra capacity, but a boxed
+        // slice is always exact.
+        if self.vec.capacity() != self.vec.len() {
+            let vec = mem::take(&mut self.vec);
+            self.vec = vec.into_boxed_slice().into_vec();
+        }
     }
 
     #[inline]
@@ -284,7 +339,7 @@ impl<Block: BlockType> VectorBase<Block> {
                 .and_then(|e| e.checked_mul(element_bits as u64))
                 .and_then(Block::checked_ceil_div_nbits)
                 .expect("VectorBase::reserve_(exact): overflow")
-                .saturating_sub(self.vec.capacity())
+                .saturating_sub(self.vec.len())
     }
 
     #[inline]
@@ -344,6 +399,18 @@ impl<'a, Block: BlockType> Iter<'a, Block> {
             data: data,
         }
     }
+
+    // PRECONDITION: start <= limit <= data.len()
+    #[inline]
+    pub fn with_range(element_bits: usize, data: &'a VectorBase<Block>,
+                      start: u64, limit: u64) -> Self {
+        Iter {
+            start,
+            limit,
+            element_bits,
+            data,
+        }
+    }
 }
 
 impl<'a, Block: BlockType> Iterator for Iter<'a, Block> {
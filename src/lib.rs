@@ -28,8 +28,13 @@
 extern crate byteorder;
 extern crate num_traits;
 
+#[cfg(feature = "serde")]
+extern crate serde;
+
 #[cfg(test)]
 extern crate quickcheck;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 #[macro_use]
 mod macros;
@@ -10,3 +10,9 @@ mod traits;
 pub use self::traits::*;
 
 mod prim;
+
+mod rrr;
+pub use self::rrr::*;
+
+mod poppy;
+pub use self::poppy::*;
@@ -0,0 +1,202 @@
+use num_traits::ToPrimitive;
+
+use bit_vec::BitVec;
+use rank::{BitRankSupport, RankSupport};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A rank structure that interleaves the bits with their counts.
+///
+/// Like `Rank9`, this divides the bits into basic blocks of eight
+/// 64-bit words and stores, for each, the number of 1s before it and a
+/// word of seven 9-bit counts within it. Unlike `Rank9`, which keeps
+/// the counts in a separate array from the bit store, here each basic
+/// block’s two count words come directly before its eight data words
+/// in a single `Vec<u64>`, in the style of Poppy. A rank query then
+/// touches one contiguous 80-byte region, rather than two unrelated
+/// cache lines.
+///
+/// The space overhead is 25%, the same as `Rank9`. Since the bits must
+/// be laid out with the counts, construction copies them.
+#[derive(Clone, Debug)]
+pub struct Poppy {
+    len: u64,
+    ones: u64,
+    data: Vec<u64>,
+}
+
+const WORDS_PER_BB: usize = 8;
+const HEADER_WORDS: usize = 2;
+const CELL_WORDS: usize = HEADER_WORDS + WORDS_PER_BB;
+
+impl Poppy {
+    /// Copies the given bits and builds the rank structure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Bits::Block` is wider than 64 bits.
+    pub fn new<Bits: BitVec + ?Sized>(bits: &Bits) -> Self {
+        assert!(Bits::Block::nbits() <= 64,
+                "Poppy::new: block type too wide");
+
+        let len = bits.bit_len();
+        let word_count = len.ceil_div(64) as usize;
+        let bb_count = word_count.ceil_div(WORDS_PER_BB);
+
+        let mut words = vec![0u64; bb_count * WORDS_PER_BB];
+        for i in 0 .. bits.block_len() {
+            let position = Bits::Block::mul_nbits(i);
+            let block = bits.get_block(i).to_u64()
+                            .expect("Poppy::new: block conversion");
+            words[(position / 64) as usize] |= block << (position % 64);
+        }
+
+        let mut data = Vec::with_capacity(bb_count * CELL_WORDS);
+        let mut ones = 0;
+
+        for bb_words in words.chunks(WORDS_PER_BB) {
+            let mut level2 = 0;
+            let mut bb_ones = 0;
+
+            for (t, word) in bb_words.iter().enumerate() {
+                if t > 0 {
+                    level2 |= bb_ones << ((t - 1) * 9);
+                }
+                bb_ones += word.count_ones();
+            }
+
+            data.push(ones);
+            data.push(level2);
+            data.extend_from_slice(bb_words);
+            ones += bb_ones;
+        }
+
+        Poppy { len, ones, data }
+    }
+
+    /// The number of bits in the vector.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the vector empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of 1 bits.
+    pub fn count_ones(&self) -> u64 {
+        self.ones
+    }
+}
+
+impl BitVec for Poppy {
+    type Block = u64;
+
+    fn bit_len(&self) -> u64 {
+        self.len
+    }
+
+    fn get_block(&self, position: usize) -> u64 {
+        let bb = position / WORDS_PER_BB;
+        let t = position % WORDS_PER_BB;
+        self.data[bb * CELL_WORDS + HEADER_WORDS + t]
+    }
+}
+
+impl RankSupport for Poppy {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl BitRankSupport for Poppy {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.len, "Poppy::rank1: out of bounds");
+
+        let word_index = (position / 64) as usize;
+        let t = word_index % WORDS_PER_BB;
+        let cell = &self.data[word_index / WORDS_PER_BB * CELL_WORDS ..];
+
+        let level2 = if t == 0 {0} else {cell[1] >> ((t - 1) * 9) & 0x1FF};
+        let word = cell[HEADER_WORDS + t];
+
+        cell[0] + level2 + word.rank1(position % 64)
+    }
+}
+
+impl SpaceUsage for Poppy {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.data.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use rank::Rank9;
+    use quickcheck::quickcheck;
+
+    fn poppy_prop(words: Vec<u32>, extra: Vec<bool>) -> bool {
+        let mut bits: BitVector<u32> = BitVector::new();
+        for &word in &words {
+            bits.push_block(word);
+        }
+        for &bit in &extra {
+            bits.push_bit(bit);
+        }
+
+        let poppy = Poppy::new(&bits);
+        let rank9 = Rank9::new(bits.clone());
+
+        poppy.bit_len() == bits.bit_len()
+            && poppy.count_ones() == bits.count_ones()
+            && (0 .. bits.bit_len()).all(|i| {
+                poppy.get_bit(i) == bits.get_bit(i)
+                    && poppy.rank1(i) == rank9.rank1(i)
+                    && poppy.rank0(i) == rank9.rank0(i)
+            })
+    }
+
+    #[test]
+    fn rank_qc() {
+        quickcheck(poppy_prop as fn(Vec<u32>, Vec<bool>) -> bool);
+    }
+
+    #[test]
+    fn large() {
+        let bits: BitVector<u8> =
+            BitVector::from_ones((0 .. 3000).map(|i| i * 7 + i % 5), 25_000);
+        let poppy = Poppy::new(&bits);
+        let rank9 = Rank9::new(bits.clone());
+
+        for i in 0 .. bits.bit_len() {
+            assert_eq!(rank9.rank1(i), poppy.rank1(i));
+        }
+        assert_eq!(3000, poppy.count_ones());
+    }
+
+    #[test]
+    fn empty() {
+        let poppy = Poppy::new(&BitVector::<u64>::new());
+        assert!(poppy.is_empty());
+        assert_eq!(0, poppy.heap_bytes());
+    }
+
+    #[test]
+    fn space() {
+        let bits: BitVector<u64> = BitVector::with_fill(1 << 16, true);
+        let poppy = Poppy::new(&bits);
+        assert_eq!(bits.heap_bytes() * 5 / 4, poppy.heap_bytes());
+        assert_eq!(1 << 16, poppy.rank1((1 << 16) - 1));
+    }
+}
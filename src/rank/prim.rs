@@ -32,6 +32,7 @@ impl_rank_support_prim!(u8);
 impl_rank_support_prim!(u16);
 impl_rank_support_prim!(u32);
 impl_rank_support_prim!(u64);
+impl_rank_support_prim!(u128);
 impl_rank_support_prim!(usize);
 
 #[cfg(test)]
@@ -1,17 +1,33 @@
-use num_traits::ToPrimitive;
+use num_traits::{PrimInt, ToPrimitive};
+
+use std::cmp;
 
 use bit_vec::BitVec;
+use broadword;
 use rank::{RankSupport, BitRankSupport};
+use select::{Select0Support, Select1Support, SelectSupport};
 use space_usage::SpaceUsage;
-use storage::BlockType;
+use storage::{Address, BlockType};
 
 /// Vigna’s rank structure for fast rank queries over a `BitVec`.
+///
+/// Rank9 counts in 64-bit words, but the underlying bit vector may use
+/// any block type up to 64 bits wide.
 #[derive(Clone, Debug)]
 pub struct Rank9<Store> {
     bit_store: Store,
     counts: Vec<Rank9Cell>,
 }
 
+/// A `Rank9` over borrowed blocks, such as a memory-mapped file.
+///
+/// Only the count arrays are allocated; the bits stay where they are.
+/// The counts are computed once, at construction, so the blocks must
+/// not change while the `Rank9Ref` exists. The borrow prevents that
+/// within Rust, but memory mapped from a file that some other process
+/// writes offers no such guarantee.
+pub type Rank9Ref<'a, Block = u64> = Rank9<&'a [Block]>;
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 struct Rank9Cell {
@@ -71,10 +87,18 @@ impl Level2 {
     }
 }
 
-impl<Store: BitVec<Block = u64>> Rank9<Store> {
+impl<Store: BitVec> Rank9<Store> {
     /// Creates a new rank9 structure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Store::Block` is wider than 64 bits.
     pub fn new(bits: Store) -> Self {
-        let bb_count = bits.block_len().ceil_div(8);
+        assert!(Store::Block::nbits() <= 64,
+                "Rank9::new: block type too wide");
+
+        let word_count = bits.bit_len().ceil_div(64) as usize;
+        let bb_count = word_count.ceil_div(8);
         let mut result = Vec::with_capacity(bb_count + 1);
 
         let mut level1_count = 0;
@@ -100,16 +124,15 @@ impl<Store: BitVec<Block = u64>> Rank9<Store> {
                 }
             };
 
-            for i in 0..bits.block_len() {
+            for i in 0..word_count {
                 store_counts(i, &mut level1_count, &mut level2_count);
 
-                let word_count = bits.get_block(i).count_ones() as u64;
+                let word_count = Self::word_count_ones(&bits, i);
                 level1_count += word_count;
                 level2_count += word_count;
             }
 
-            store_counts(bits.block_len(),
-                         &mut level1_count, &mut level2_count);
+            store_counts(word_count, &mut level1_count, &mut level2_count);
         }
 
         Rank9 {
@@ -118,6 +141,34 @@ impl<Store: BitVec<Block = u64>> Rank9<Store> {
         }
     }
 
+    // The number of blocks making up each 64-bit word.
+    fn blocks_per_word() -> usize {
+        64 / Store::Block::nbits()
+    }
+
+    // Counts the ones in the `word_index`th 64-bit word.
+    fn word_count_ones(bits: &Store, word_index: usize) -> u64 {
+        let start = word_index * Self::blocks_per_word();
+        let limit = cmp::min(start + Self::blocks_per_word(),
+                             bits.block_len());
+
+        (start .. limit)
+            .map(|i| bits.get_block(i).count_ones() as u64)
+            .sum()
+    }
+
+    // Assembles the `word_index`th 64-bit word from its blocks.
+    fn get_word(&self, word_index: usize) -> u64 {
+        let start = word_index * Self::blocks_per_word();
+        let limit = cmp::min(start + Self::blocks_per_word(),
+                             self.bit_store.block_len());
+
+        (start .. limit).fold(0, |word, i| {
+            let block = self.bit_store.get_block(i).to_u64().unwrap();
+            word | block << Store::Block::mul_nbits(i - start)
+        })
+    }
+
     /// Borrows a reference to the underlying bit store.
     pub fn inner(&self) -> &Store {
         &self.bit_store
@@ -129,27 +180,34 @@ impl<Store: BitVec<Block = u64>> Rank9<Store> {
     }
 }
 
-impl<Store: BitVec<Block = u64>> BitRankSupport for Rank9<Store> {
+impl<Store: BitVec> BitRankSupport for Rank9<Store> {
     fn rank1(&self, position: u64) -> u64 {
         let bb_index = (position / 512).to_usize()
                                        .expect("Rank9::rank1: index overflow");
         let word_index = (position / 64).to_usize()
                                         .expect("Rank9::rank1: index overflow");
         let word_offset = word_index % 8;
-        let bit_offset = position % 64;
 
         let cell = self.counts[bb_index];
 
         let bb_portion = cell.level1;
         let word_portion = cell.level2.get(word_offset);
-        let bit_portion = self.bit_store.get_block(word_index)
-                                        .rank1(bit_offset);
+
+        // Within the word, count the whole blocks before the one
+        // containing `position`, then rank within that block.
+        let address = Address::new::<Store::Block>(position);
+        let first_block = word_index * Self::blocks_per_word();
+        let bit_portion = (first_block .. address.block_index)
+            .map(|i| self.bit_store.get_block(i).count_ones() as u64)
+            .sum::<u64>()
+            + self.bit_store.get_block(address.block_index)
+                            .rank1(address.bit_offset as u64);
 
         bb_portion + word_portion + bit_portion
     }
 }
 
-impl<Store: BitVec<Block = u64>> RankSupport for Rank9<Store> {
+impl<Store: BitVec> RankSupport for Rank9<Store> {
     type Over = bool;
 
     fn rank(&self, position: u64, value: bool) -> u64 {
@@ -161,8 +219,183 @@ impl<Store: BitVec<Block = u64>> RankSupport for Rank9<Store> {
     }
 }
 
-impl<Store: BitVec<Block = u64>> BitVec for Rank9<Store> {
-    impl_bit_vec_adapter!(u64, bit_store);
+impl<Store: BitVec> BitVec for Rank9<Store> {
+    impl_bit_vec_adapter!(Store::Block, bit_store);
+}
+
+/// Select support built on the counts of a `Rank9`.
+///
+/// In addition to the rank structure, this records which basic block
+/// (512 bits) contains every 512th 1 and every 512th 0. A select query
+/// then binary searches the level-1 counts between two samples, scans
+/// the level-2 counts of the basic block, and finishes with broadword
+/// select within a word.
+#[derive(Clone, Debug)]
+pub struct Rank9Select<Store> {
+    rank9: Rank9<Store>,
+    ones: u64,
+    samples1: Vec<usize>,
+    samples0: Vec<usize>,
+}
+
+const SELECT_SAMPLE_RATE: u64 = 512;
+
+impl<Store: BitVec> Rank9Select<Store> {
+    /// Creates a new rank and select structure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Store::Block` is wider than 64 bits.
+    pub fn new(bits: Store) -> Self {
+        let rank9 = Rank9::new(bits);
+        let len = rank9.limit();
+        let ones = if len == 0 {0} else {rank9.rank1(len - 1)};
+
+        let mut result = Rank9Select {
+            rank9,
+            ones,
+            samples1: Vec::new(),
+            samples0: Vec::new(),
+        };
+
+        result.samples1 = result.sample(true);
+        result.samples0 = result.sample(false);
+        result
+    }
+
+    /// Borrows a reference to the underlying rank structure.
+    pub fn inner(&self) -> &Rank9<Store> {
+        &self.rank9
+    }
+
+    /// Returns the underlying rank structure.
+    pub fn into_inner(self) -> Rank9<Store> {
+        self.rank9
+    }
+
+    fn bb_count(&self) -> usize {
+        self.rank9.limit().ceil_div(512) as usize
+    }
+
+    // The number of occurrences of `value` before basic block `bb`,
+    // for `bb` up to and including `bb_count()`.
+    fn count_before(&self, bb: usize, value: bool) -> u64 {
+        let ones = if bb < self.bb_count() {
+            self.rank9.counts[bb].level1
+        } else {
+            self.ones
+        };
+
+        if value {
+            ones
+        } else {
+            cmp::min(bb as u64 * 512, self.rank9.limit()) - ones
+        }
+    }
+
+    fn sample(&self, value: bool) -> Vec<usize> {
+        let mut samples = Vec::new();
+        let mut next = 0;
+
+        for bb in 0 .. self.bb_count() {
+            while next < self.count_before(bb + 1, value) {
+                samples.push(bb);
+                next += SELECT_SAMPLE_RATE;
+            }
+        }
+
+        samples
+    }
+
+    fn select_bit(&self, index: u64, value: bool) -> Option<u64> {
+        if index >= self.count_before(self.bb_count(), value) {
+            return None;
+        }
+
+        // The samples bound the basic block; binary search for the
+        // last one that starts at or before the `index`th occurrence.
+        let samples = if value {&self.samples1} else {&self.samples0};
+        let sample = (index / SELECT_SAMPLE_RATE) as usize;
+        let mut lo = samples[sample];
+        let mut hi = samples.get(sample + 1).cloned()
+                            .unwrap_or(self.bb_count() - 1);
+
+        while lo < hi {
+            let mid = lo + (hi - lo).ceil_div(2);
+            if self.count_before(mid, value) <= index {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        let bb = lo;
+        let mut remaining = index - self.count_before(bb, value);
+
+        // Then scan the level-2 counts for the word.
+        let level2 = self.rank9.counts[bb].level2;
+        let word_count = self.rank9.limit().ceil_div(64) as usize;
+        let words_in_bb = cmp::min(8, word_count - bb * 8);
+        let word_before = |w: usize| {
+            if value {level2.get(w)} else {w as u64 * 64 - level2.get(w)}
+        };
+
+        let mut word_offset = 0;
+        for w in 1 .. words_in_bb {
+            if word_before(w) > remaining { break }
+            word_offset = w;
+        }
+        remaining -= word_before(word_offset);
+
+        let word_index = bb * 8 + word_offset;
+        let word = self.rank9.get_word(word_index);
+        let word = if value {word} else {!word};
+
+        broadword::select1(remaining as usize, word)
+            .map(|offset| word_index as u64 * 64 + offset as u64)
+    }
+}
+
+impl<Store: BitVec> Select1Support for Rank9Select<Store> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        self.select_bit(index, true)
+    }
+}
+
+impl<Store: BitVec> Select0Support for Rank9Select<Store> {
+    fn select0(&self, index: u64) -> Option<u64> {
+        self.select_bit(index, false)
+    }
+}
+
+impl<Store: BitVec> SelectSupport for Rank9Select<Store> {
+    type Over = bool;
+
+    fn select(&self, index: u64, value: bool) -> Option<u64> {
+        self.select_bit(index, value)
+    }
+}
+
+impl<Store: BitVec> BitRankSupport for Rank9Select<Store> {
+    impl_bit_rank_support_adapter!(rank9);
+}
+
+impl<Store: BitVec> RankSupport for Rank9Select<Store> {
+    impl_rank_support_adapter!(bool, rank9);
+}
+
+impl<Store: BitVec> BitVec for Rank9Select<Store> {
+    impl_bit_vec_adapter!(Store::Block, rank9);
+}
+
+impl<Store: SpaceUsage> SpaceUsage for Rank9Select<Store> {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.rank9.heap_bytes()
+            + self.samples1.heap_bytes()
+            + self.samples0.heap_bytes()
+    }
 }
 
 impl_stack_only_space_usage!(Rank9Cell);
@@ -215,8 +448,12 @@ fn level2() {
 
 #[cfg(test)]
 mod test {
+    use quickcheck::quickcheck;
+
     use super::*;
+    use bit_vec::BitVec;
     use rank::BitRankSupport;
+    use select::{Select0Support, Select1Support};
 
     #[test]
     fn rank1() {
@@ -241,6 +478,142 @@ mod test {
         assert_eq!(4096, rank.rank1(1024 * 64 - 1));
     }
 
+    #[test]
+    fn borrowed() {
+        let words: Vec<u64> = (0 .. 100u64)
+            .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+
+        let borrowed: Rank9Ref = Rank9::new(&words[..]);
+        let owned = Rank9::new(words.clone());
+
+        for i in 0 .. words.bit_len() {
+            assert_eq!(owned.rank1(i), borrowed.rank1(i));
+        }
+    }
+
+    #[test]
+    fn narrow_blocks() {
+        use bit_vec::{BitVecPush, BitVector};
+
+        let mut bv16: BitVector<u16> = BitVector::new();
+        let mut bv32: BitVector<u32> = BitVector::new();
+        let mut bv64: BitVector<u64> = BitVector::new();
+
+        for i in 0 .. 2000u64 {
+            let bit = (i * i) % 7 < 3 || i % 11 == 0;
+            bv16.push_bit(bit);
+            bv32.push_bit(bit);
+            bv64.push_bit(bit);
+        }
+
+        let rank16 = Rank9::new(bv16);
+        let rank32 = Rank9::new(bv32);
+        let rank64 = Rank9::new(bv64);
+
+        for i in 0 .. 2000 {
+            assert_eq!(rank64.rank1(i), rank16.rank1(i));
+            assert_eq!(rank64.rank1(i), rank32.rank1(i));
+        }
+    }
+
+    #[test]
+    fn rank1_u32() {
+        let vec = vec![ 0b00000000000001110000000000000001u32; 1024 ];
+        let rank = Rank9::new(vec);
+
+        assert_eq!(1, rank.rank1(0));
+        assert_eq!(4, rank.rank1(31));
+        assert_eq!(5, rank.rank1(32));
+        assert_eq!(2048, rank.rank1(512 * 32 - 1));
+        assert_eq!(4096, rank.rank1(1024 * 32 - 1));
+    }
+
+    fn select_prop(words: Vec<u32>, density: u8) -> bool {
+        // Thin out the 1s sometimes, so that sparse vectors get tested.
+        let shift = density % 4 * 8;
+        let words: Vec<u32> = words.iter()
+            .map(|&w| if shift == 0 {w} else {w & w >> shift})
+            .collect();
+
+        let len = words.bit_len();
+        let (ones, zeros): (Vec<u64>, Vec<u64>) =
+            (0 .. len).partition(|&i| words.get_bit(i));
+
+        let select = Rank9Select::new(&*words);
+        (0 .. len + 1).all(|i| {
+            select.select1(i) == ones.get(i as usize).cloned()
+                && select.select0(i) == zeros.get(i as usize).cloned()
+        })
+    }
+
+    #[test]
+    fn select_qc() {
+        quickcheck(select_prop as fn(Vec<u32>, u8) -> bool);
+    }
+
+    #[test]
+    fn select_large() {
+        use bit_vec::{BitVecPush, BitVector};
+
+        let mut bv: BitVector<u64> = BitVector::new();
+        for i in 0 .. 20_000u64 {
+            bv.push_bit(i % 7 == 0 || (i / 1000) % 3 == 1);
+        }
+
+        let (ones, zeros): (Vec<u64>, Vec<u64>) =
+            (0 .. 20_000).partition(|&i| bv.get_bit(i));
+
+        let select = Rank9Select::new(bv);
+        for (index, &position) in ones.iter().enumerate() {
+            assert_eq!(Some(position), select.select1(index as u64));
+        }
+        for (index, &position) in zeros.iter().enumerate() {
+            assert_eq!(Some(position), select.select0(index as u64));
+        }
+        assert_eq!(None, select.select1(ones.len() as u64));
+        assert_eq!(None, select.select0(zeros.len() as u64));
+    }
+
+    #[test]
+    fn select_space() {
+        use space_usage::*;
+
+        let vec = vec![ 0x5555_5555_5555_5555u64; 1000 ];
+        let rank = Rank9::new(vec.clone());
+        let select = Rank9Select::new(vec);
+
+        // 32,000 of each bit value makes 63 samples apiece.
+        let samples = 2 * 63 * ::std::mem::size_of::<usize>();
+        assert!(select.heap_bytes() >= rank.heap_bytes() + samples);
+        assert!(select.heap_bytes() < rank.heap_bytes() + 2 * samples);
+    }
+
+    #[test]
+    fn int_vector_bits() {
+        use int_vec::IntVector;
+        use select::BinSearchSelect;
+
+        // Treat a vector of 5-bit elements as a bitmap: element `i`
+        // contributes bits `5 * i .. 5 * i + 5`.
+        let mut ints = IntVector::<u32>::new(5);
+        for i in 0 .. 1000u32 {
+            ints.push(i % 32);
+        }
+
+        let rank = Rank9::new(&ints);
+        let mut expected = 0;
+        for i in 0 .. ints.bit_len() {
+            expected += ints.get_bit(i) as u64;
+            assert_eq!(expected, rank.rank1(i));
+        }
+
+        let select = BinSearchSelect::new(rank);
+        assert_eq!(Some(5), select.select1(0));
+        assert_eq!(Some(11), select.select1(1));
+        assert_eq!(Some(15), select.select1(2));
+    }
+
     // This test is a sanity check that we aren’t taking up too much
     // space with the metadata.
     #[test]
@@ -0,0 +1,324 @@
+use num_traits::ToPrimitive;
+
+use std::cmp;
+
+use bit_vec::{BitVec, BitVecPush, BitVector};
+use coding::enum_code;
+use int_vec::{IntVec, IntVector};
+use rank::{BitRankSupport, RankSupport};
+use select::Select1Support;
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// An RRR compressed bit vector with rank and select support.
+///
+/// The bits are divided into blocks of `block_size` bits. Each block is
+/// stored as its class (the number of 1s it contains) and its offset,
+/// the index of the block among all blocks of that class. Since the
+/// offset of a block with class `c` only needs `lg (block_size choose
+/// c)` bits, sparse and dense bit vectors compress well. Every
+/// `superblock_factor` blocks we sample the rank and the position in
+/// the offset stream, so a query decodes at most that many classes and
+/// one offset.
+///
+/// Larger blocks compress better but make each query slower; the usual
+/// choices are 15, 31, and 63.
+///
+/// Construct with `RrrVec::new`.
+#[derive(Clone, Debug)]
+pub struct RrrVec {
+    len: u64,
+    block_size: usize,
+    superblock_factor: usize,
+    ones: u64,
+    classes: IntVector<u64>,
+    offsets: BitVector<u64>,
+    superblock_ranks: Vec<u64>,
+    superblock_positions: Vec<u64>,
+    binomials: Vec<u64>,
+}
+
+impl RrrVec {
+    /// Compresses the given bit vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= block_size <= 63` and `superblock_factor >= 1`.
+    pub fn new<Bits: BitVec + ?Sized>(bits: &Bits,
+                                      block_size: usize,
+                                      superblock_factor: usize) -> Self {
+        assert!((1 .. 64).contains(&block_size),
+                "RrrVec::new: block_size must be from 1 to 63");
+        assert!(superblock_factor >= 1,
+                "RrrVec::new: superblock_factor must be positive");
+
+        let len = bits.bit_len();
+        let block_count = len.ceil_div(block_size as u64);
+        let binomials = (0 .. block_size + 1)
+            .map(|class| enum_code::binomial(block_size, class))
+            .collect();
+
+        let mut result = RrrVec {
+            len,
+            block_size,
+            superblock_factor,
+            ones: 0,
+            classes: IntVector::with_capacity((block_size + 1).ceil_lg(),
+                                              block_count),
+            offsets: BitVector::new(),
+            superblock_ranks: Vec::new(),
+            superblock_positions: Vec::new(),
+            binomials,
+        };
+
+        for i in 0 .. block_count {
+            if i % superblock_factor as u64 == 0 {
+                result.superblock_ranks.push(result.ones);
+                result.superblock_positions.push(result.offsets.bit_len());
+            }
+
+            let start = i * block_size as u64;
+            let count = cmp::min(block_size as u64, len - start) as usize;
+            let block = read_bits(bits, start, count);
+            let class = block.count_ones() as usize;
+
+            let offset_bits = result.offset_bits(class);
+            let offset = enum_code::encode(block_size, class, block);
+            for j in 0 .. offset_bits {
+                result.offsets.push_bit(offset.get_bit(j));
+            }
+
+            result.classes.push(class as u64);
+            result.ones += class as u64;
+        }
+
+        result.offsets.shrink_to_fit();
+        result
+    }
+
+    /// The number of bits in the vector.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Is the vector empty?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of bits in each block.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// The number of blocks between rank samples.
+    pub fn superblock_factor(&self) -> usize {
+        self.superblock_factor
+    }
+
+    /// The total number of 1 bits.
+    pub fn count_ones(&self) -> u64 {
+        self.ones
+    }
+
+    /// Returns the bit at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.len()`.
+    pub fn get_bit(&self, position: u64) -> bool {
+        assert!(position < self.len, "RrrVec::get_bit: out of bounds");
+
+        let block_index = (position / self.block_size as u64) as usize;
+        let (_, block) = self.decode_block(block_index);
+        block.get_bit((position % self.block_size as u64) as usize)
+    }
+
+    // The number of bits used by the offset of a block of class `class`.
+    fn offset_bits(&self, class: usize) -> usize {
+        self.binomials[class].ceil_lg()
+    }
+
+    // Decodes the `block_index`th block, also returning the number of
+    // 1s before it.
+    fn decode_block(&self, block_index: usize) -> (u64, u64) {
+        let superblock = block_index / self.superblock_factor;
+        let mut rank = self.superblock_ranks[superblock];
+        let mut position = self.superblock_positions[superblock];
+
+        for i in superblock * self.superblock_factor .. block_index {
+            let class = self.classes.get(i as u64) as usize;
+            rank += class as u64;
+            position += self.offset_bits(class) as u64;
+        }
+
+        let class = self.classes.get(block_index as u64) as usize;
+        let offset_bits = self.offset_bits(class);
+        let offset = if offset_bits == 0 {
+            0
+        } else {
+            self.offsets.get_bits(position, offset_bits)
+        };
+
+        (rank, enum_code::decode(self.block_size, class, offset))
+    }
+}
+
+impl RankSupport for RrrVec {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.len
+    }
+}
+
+impl BitRankSupport for RrrVec {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.len, "RrrVec::rank1: out of bounds");
+
+        let block_index = (position / self.block_size as u64) as usize;
+        let bit_offset = (position % self.block_size as u64) as usize;
+        let (rank, block) = self.decode_block(block_index);
+
+        rank + block.rank1(bit_offset as u64)
+    }
+}
+
+impl Select1Support for RrrVec {
+    fn select1(&self, index: u64) -> Option<u64> {
+        if index >= self.ones { return None; }
+
+        // The last superblock whose rank sample is at most `index`.
+        let superblock = match self.superblock_ranks.binary_search(&index) {
+            Ok(mut i) => {
+                // Several superblocks may share a sample; take the last.
+                while i + 1 < self.superblock_ranks.len()
+                    && self.superblock_ranks[i + 1] == index {
+                    i += 1;
+                }
+                i
+            }
+            Err(i) => i - 1,
+        };
+
+        let mut rank = self.superblock_ranks[superblock];
+        let mut block_index = superblock * self.superblock_factor;
+        loop {
+            let class = self.classes.get(block_index as u64);
+            if rank + class > index { break; }
+            rank += class;
+            block_index += 1;
+        }
+
+        let (_, block) = self.decode_block(block_index);
+        block.select1((index - rank) as usize).map(|offset| {
+            block_index as u64 * self.block_size as u64 + offset as u64
+        })
+    }
+}
+
+impl SpaceUsage for RrrVec {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.classes.heap_bytes()
+            + self.offsets.heap_bytes()
+            + self.superblock_ranks.heap_bytes()
+            + self.superblock_positions.heap_bytes()
+            + self.binomials.heap_bytes()
+    }
+}
+
+// Reads `count <= 64` bits starting at `start`, one `Bits::Block` at a
+// time.
+fn read_bits<Bits: BitVec + ?Sized>(bits: &Bits, start: u64, count: usize)
+                                    -> u64 {
+    let nbits = Bits::Block::nbits();
+    let mut result = 0;
+    let mut filled = 0;
+
+    while filled < count {
+        let chunk = cmp::min(nbits, count - filled);
+        let value = bits.get_bits(start + filled as u64, chunk)
+                        .to_u64().unwrap();
+        result |= value << filled;
+        filled += chunk;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn rrr_prop(words: Vec<u32>, block_size: u8, factor: u8) -> bool {
+        let block_size = block_size as usize % 63 + 1;
+        let factor = factor as usize % 8 + 1;
+        let len = words.bit_len();
+
+        let rrr = RrrVec::new(&*words, block_size, factor);
+        let ones: Vec<u64> = (0 .. len).filter(|&i| words.get_bit(i))
+                                       .collect();
+
+        let mut rank = 0;
+        (0 .. len).all(|i| {
+            if words.get_bit(i) { rank += 1; }
+            rrr.get_bit(i) == words.get_bit(i) && rrr.rank1(i) == rank
+        }) && (0 .. ones.len() as u64 + 1).all(|i| {
+            rrr.select1(i) == ones.get(i as usize).cloned()
+        })
+    }
+
+    #[test]
+    fn rank_select_qc() {
+        quickcheck(rrr_prop as fn(Vec<u32>, u8, u8) -> bool);
+    }
+
+    #[test]
+    fn block_sizes() {
+        let mut bits: BitVector<u64> = BitVector::new();
+        for i in 0 .. 5000u64 {
+            bits.push_bit(i % 13 == 0 || (i / 700) % 4 == 3);
+        }
+
+        for &block_size in &[15, 31, 63] {
+            let rrr = RrrVec::new(&bits, block_size, 16);
+            assert_eq!(5000, rrr.len());
+            assert_eq!(block_size, rrr.block_size());
+            assert_eq!(bits.count_ones(), rrr.count_ones());
+
+            let mut rank = 0;
+            for i in 0 .. 5000 {
+                if bits.get_bit(i) {
+                    assert_eq!(Some(i), rrr.select1(rank));
+                    rank += 1;
+                }
+                assert_eq!(rank, rrr.rank1(i));
+            }
+            assert_eq!(None, rrr.select1(rank));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let rrr = RrrVec::new(&BitVector::<u64>::new(), 15, 4);
+        assert!(rrr.is_empty());
+        assert_eq!(None, rrr.select1(0));
+    }
+
+    #[test]
+    fn compresses_sparse() {
+        let bits: BitVector<u64> =
+            BitVector::from_ones((0 .. 100).map(|i| i * 1000), 100_000);
+        let rrr = RrrVec::new(&bits, 63, 32);
+
+        assert!(rrr.heap_bytes() < bits.heap_bytes() / 2);
+        assert_eq!(Some(42_000), rrr.select1(42));
+    }
+}
@@ -22,6 +22,9 @@ pub trait RankSupport {
 }
 
 /// Supports fast rank queries over `bool`s.
+///
+/// Since `Over` is fixed to `bool`, this trait is object safe, so a
+/// `Box<dyn BitRankSupport>` can hold any of the rank structures.
 pub trait BitRankSupport: RankSupport<Over = bool> {
     /// Returns the rank of 1 at the given position.
     ///
@@ -39,3 +42,45 @@ pub trait BitRankSupport: RankSupport<Over = bool> {
         position + 1 - self.rank1(position)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVector;
+    use rank::{JacobsonRank, Poppy, Rank9, Rank9Select, RrrVec};
+    use select::{SArray, Select1Support};
+
+    #[test]
+    fn trait_objects() {
+        let ones: Vec<u64> = (0 .. 300).map(|i| i * 7 + i % 3).collect();
+        let bits: BitVector<u64> = BitVector::from_ones(ones.clone(), 2200);
+
+        let ranks: Vec<Box<dyn BitRankSupport>> = vec![
+            Box::new(Rank9::new(bits.clone())),
+            Box::new(JacobsonRank::new(bits.clone())),
+            Box::new(Poppy::new(&bits)),
+            Box::new(RrrVec::new(&bits, 15, 4)),
+        ];
+
+        let selects: Vec<Box<dyn Select1Support>> = vec![
+            Box::new(Rank9Select::new(bits.clone())),
+            Box::new(SArray::from_ones(&ones, 2200)),
+            Box::new(bits.clone()),
+        ];
+
+        for rank in &ranks {
+            assert_eq!(2200, rank.limit());
+            for i in 0 .. 2200 {
+                assert_eq!(bits.rank1(i), rank.rank1(i));
+                assert_eq!(bits.rank0(i), rank.rank0(i));
+            }
+        }
+
+        for select in &selects {
+            for (i, &position) in ones.iter().enumerate() {
+                assert_eq!(Some(position), select.select1(i as u64));
+            }
+            assert_eq!(None, select.select1(ones.len() as u64));
+        }
+    }
+}
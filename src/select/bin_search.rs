@@ -5,8 +5,15 @@ use bit_vec::BitVec;
 use super::{SelectSupport, Select1Support, Select0Support};
 
 /// Performs a select query by binary searching rank queries.
+///
+/// Optionally, the position of every `stride`th 1 can be sampled (see
+/// [`with_sampling`](#method.with_sampling)), which narrows the binary
+/// search for `select1` to the interval between two samples.
+#[derive(Clone, Debug)]
 pub struct BinSearchSelect<Rank> {
     rank_support: Rank,
+    stride: u64,
+    samples: Vec<u64>,
 }
 
 /// Creates a new binary search select support based on a rank support.
@@ -16,6 +23,8 @@ impl<Rank: RankSupport> BinSearchSelect<Rank> {
     pub fn new(rank_support: Rank) -> Self {
         BinSearchSelect {
             rank_support: rank_support,
+            stride: 0,
+            samples: Vec::new(),
         }
     }
 
@@ -30,6 +39,31 @@ impl<Rank: RankSupport> BinSearchSelect<Rank> {
     }
 }
 
+impl<Rank: BitRankSupport> BinSearchSelect<Rank> {
+    /// Creates a new binary search selection support that also samples
+    /// the position of every `stride`th 1 bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is 0.
+    pub fn with_sampling(rank_support: Rank, stride: u64) -> Self {
+        assert!(stride > 0, "BinSearchSelect::with_sampling: zero stride");
+
+        let mut result = Self::new(rank_support);
+        let mut samples = Vec::new();
+        let mut index = 0;
+
+        while let Some(position) = result.select1(index) {
+            samples.push(position);
+            index += stride;
+        }
+
+        result.stride = stride;
+        result.samples = samples;
+        result
+    }
+}
+
 impl<Rank: BitVec> BitVec for BinSearchSelect<Rank> {
     impl_bit_vec_adapter!(Rank::Block, rank_support);
 }
@@ -60,9 +94,28 @@ macro_rules! impl_select_support_b {
     }
 }
 
-impl_select_support_b!(Select1Support, select1, rank1);
 impl_select_support_b!(Select0Support, select0, rank0);
 
+impl<Rank: BitRankSupport> Select1Support for BinSearchSelect<Rank> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        let (start, limit) = if self.samples.is_empty() {
+            (0, self.limit())
+        } else {
+            let sample = (index / self.stride) as usize;
+            match self.samples.get(sample) {
+                Some(&start) => {
+                    let limit = self.samples.get(sample + 1)
+                                    .map_or(self.limit(), |&next| next + 1);
+                    (start, limit)
+                }
+                None => return None,
+            }
+        };
+
+        binary_search_function(start, limit, index + 1, |i| self.rank1(i))
+    }
+}
+
 impl<Rank: RankSupport> SelectSupport for BinSearchSelect<Rank> {
     type Over = Rank::Over;
 
@@ -73,8 +126,11 @@ impl<Rank: RankSupport> SelectSupport for BinSearchSelect<Rank> {
 }
 
 impl<Rank: SpaceUsage> SpaceUsage for BinSearchSelect<Rank> {
-    fn is_stack_only() -> bool { Rank::is_stack_only() }
-    fn heap_bytes(&self) -> usize { self.rank_support.heap_bytes() }
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.rank_support.heap_bytes() + self.samples.heap_bytes()
+    }
 }
 
 #[cfg(test)]
@@ -139,4 +195,64 @@ mod test {
         assert_eq!(Some(32767), select.select1(32767));
         assert_eq!(None, select.select1(32768));
     }
+
+    #[test]
+    fn select0() {
+        let vec = vec![ 0b11111111111111101111111111111110u32; 1024 ];
+        let select = BinSearchSelect::new(JacobsonRank::new(vec));
+
+        assert_eq!(Some(0), select.select0(0));
+        assert_eq!(Some(16), select.select0(1));
+        assert_eq!(Some(32), select.select0(2));
+        assert_eq!(Some(32 * 1023 + 16), select.select0(2047));
+        assert_eq!(None, select.select0(2048));
+    }
+
+    #[test]
+    fn select0_qc() {
+        use bit_vec::BitVec;
+        use quickcheck::quickcheck;
+
+        fn prop(words: Vec<u32>) -> bool {
+            let zeros: Vec<u64> = (0 .. words.bit_len())
+                .filter(|&i| !words.get_bit(i))
+                .collect();
+            let select = BinSearchSelect::new(Rank9::new(&*words));
+
+            (0 .. zeros.len() as u64 + 1).all(|i| {
+                select.select0(i) == zeros.get(i as usize).cloned()
+            })
+        }
+
+        quickcheck(prop as fn(Vec<u32>) -> bool);
+    }
+
+    #[test]
+    fn with_sampling() {
+        use bit_vec::{BitVecPush, BitVector};
+        use space_usage::SpaceUsage;
+        use storage::BlockType;
+
+        let mut bv: BitVector<u64> = BitVector::new();
+        for i in 0 .. 10_000u64 {
+            bv.push_bit(i % 7 == 0 || (i / 500) % 5 == 2);
+        }
+
+        let plain = BinSearchSelect::new(Rank9::new(bv.clone()));
+        let ones = plain.rank1(9_999);
+
+        for &stride in &[1, 3, 64, 1000, 100_000] {
+            let sampled =
+                BinSearchSelect::with_sampling(Rank9::new(bv.clone()),
+                                               stride);
+
+            for i in 0 .. ones + 2 {
+                assert_eq!(plain.select1(i), sampled.select1(i));
+            }
+
+            let samples = ones.ceil_div(stride) as usize;
+            assert!(sampled.heap_bytes() >= plain.heap_bytes()
+                        + samples * 8);
+        }
+    }
 }
@@ -0,0 +1,246 @@
+use num_traits::PrimInt;
+
+use bit_vec::BitVec;
+use space_usage::SpaceUsage;
+use storage::{Address, BlockType};
+use super::Select1Support;
+
+/// Okanohara and Sadakane’s dArray, for fast select queries over a
+/// `BitVec`.
+///
+/// The 1s are divided into blocks of 1024. A block spanning at least
+/// 2<sup>16</sup> bits is *sparse*, and we store the position of each
+/// of its 1s outright, which costs little since there are few of them
+/// relative to the bits. Otherwise the block is *dense*, and we store
+/// its start and the 16-bit offset of every 32nd 1 within it, so a
+/// query scans at most 2<sup>16</sup> bits, and in practice far fewer.
+///
+/// Unlike `BinSearchSelect`, this needs no rank support, so it suits
+/// workloads that are heavy on select and light on rank.
+///
+/// Construct with `DArray::new`.
+#[derive(Clone, Debug)]
+pub struct DArray<Store> {
+    bit_store: Store,
+    ones: u64,
+    blocks: Vec<DArrayBlock>,
+    positions: Vec<u64>,
+    subsamples: Vec<u16>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum DArrayBlock {
+    // The position of the block’s first 1, and the index of its first
+    // subsample.
+    Dense { start: u64, subsamples: usize },
+    // The index of the block’s first position.
+    Sparse { positions: usize },
+}
+
+impl_stack_only_space_usage!(DArrayBlock);
+
+const ONES_PER_BLOCK: usize = 1024;
+const SPARSE_SPAN: u64 = 1 << 16;
+const SUBSAMPLE_RATE: usize = 32;
+
+impl<Store: BitVec> DArray<Store> {
+    /// Creates a new select structure for the given bit vector.
+    pub fn new(bits: Store) -> Self {
+        let mut blocks = Vec::new();
+        let mut positions = Vec::new();
+        let mut subsamples = Vec::new();
+        let mut ones = 0;
+
+        {
+            let mut add_block = |block: &[u64]| {
+                let start = block[0];
+                let span = block[block.len() - 1] - start + 1;
+
+                if span >= SPARSE_SPAN {
+                    blocks.push(DArrayBlock::Sparse {
+                        positions: positions.len(),
+                    });
+                    positions.extend_from_slice(block);
+                } else {
+                    blocks.push(DArrayBlock::Dense {
+                        start,
+                        subsamples: subsamples.len(),
+                    });
+                    subsamples.extend(block.iter().step_by(SUBSAMPLE_RATE)
+                                           .map(|&p| (p - start) as u16));
+                }
+            };
+
+            let mut block = Vec::with_capacity(ONES_PER_BLOCK);
+            for position in bits.iter_ones() {
+                block.push(position);
+                ones += 1;
+
+                if block.len() == ONES_PER_BLOCK {
+                    add_block(&block);
+                    block.clear();
+                }
+            }
+
+            if !block.is_empty() {
+                add_block(&block);
+            }
+        }
+
+        DArray {
+            bit_store: bits,
+            ones,
+            blocks,
+            positions,
+            subsamples,
+        }
+    }
+
+    // Finds the `rank`th 1 at or after `position`, which must exist.
+    fn scan(&self, position: u64, mut rank: usize) -> u64 {
+        let address = Address::new::<Store::Block>(position);
+        let mut index = address.block_index;
+        let mut block = self.bit_store.get_block(index)
+            & !Store::Block::low_mask(address.bit_offset);
+
+        loop {
+            let count = block.count_ones() as usize;
+            if rank < count {
+                let offset = block.select1(rank)
+                                  .expect("DArray::scan: select1 failed");
+                return Store::Block::mul_nbits(index) + offset as u64;
+            }
+
+            rank -= count;
+            index += 1;
+            block = self.bit_store.get_block(index);
+        }
+    }
+
+    /// The number of 1 bits.
+    pub fn count_ones(&self) -> u64 {
+        self.ones
+    }
+
+    /// Borrows a reference to the underlying bit store.
+    pub fn inner(&self) -> &Store {
+        &self.bit_store
+    }
+
+    /// Returns the underlying bit store.
+    pub fn into_inner(self) -> Store {
+        self.bit_store
+    }
+}
+
+impl<Store: BitVec> Select1Support for DArray<Store> {
+    fn select1(&self, index: u64) -> Option<u64> {
+        if index >= self.ones { return None; }
+
+        let block = (index / ONES_PER_BLOCK as u64) as usize;
+        let within = (index % ONES_PER_BLOCK as u64) as usize;
+
+        match self.blocks[block] {
+            DArrayBlock::Sparse { positions } =>
+                Some(self.positions[positions + within]),
+            DArrayBlock::Dense { start, subsamples } => {
+                let subsample = self.subsamples[
+                    subsamples + within / SUBSAMPLE_RATE];
+                Some(self.scan(start + subsample as u64,
+                               within % SUBSAMPLE_RATE))
+            }
+        }
+    }
+}
+
+impl<Store: BitVec> BitVec for DArray<Store> {
+    impl_bit_vec_adapter!(Store::Block, bit_store);
+}
+
+impl<Store: SpaceUsage> SpaceUsage for DArray<Store> {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.bit_store.heap_bytes()
+            + self.blocks.heap_bytes()
+            + self.positions.heap_bytes()
+            + self.subsamples.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVecPush, BitVector};
+    use rank::Rank9;
+    use select::BinSearchSelect;
+    use quickcheck::quickcheck;
+
+    fn check_against_bin_search(bits: BitVector<u32>) -> bool {
+        let darray = DArray::new(bits.clone());
+        let bin_search = BinSearchSelect::new(Rank9::new(bits));
+        let ones = darray.count_ones();
+
+        (0 .. ones + 2).all(|i| darray.select1(i) == bin_search.select1(i))
+    }
+
+    fn darray_prop(runs: Vec<(bool, u16)>) -> bool {
+        let mut bits = BitVector::new();
+        for (value, len) in runs {
+            let len = len as u64 % 5000;
+            let start = bits.bit_len();
+            bits.resize(start + len, value);
+            bits.push_bit(!value);
+        }
+
+        check_against_bin_search(bits)
+    }
+
+    #[test]
+    fn select1_qc() {
+        quickcheck(darray_prop as fn(Vec<(bool, u16)>) -> bool);
+    }
+
+    #[test]
+    fn dense() {
+        let bits = BitVector::with_fill(100_000, true);
+        let darray = DArray::new(bits.clone());
+        assert!(darray.positions.is_empty());
+        assert!(check_against_bin_search(bits));
+    }
+
+    #[test]
+    fn sparse() {
+        let bits = BitVector::from_ones((0 .. 3000).map(|i| i * 100 + i % 7),
+                                        300_000);
+        let darray = DArray::new(bits.clone());
+        assert!(darray.subsamples.is_empty());
+        assert_eq!(3000, darray.positions.len());
+        assert!(check_against_bin_search(bits));
+    }
+
+    #[test]
+    fn mixed() {
+        // A dense run, a sparse stretch, then another dense run.
+        let mut bits: BitVector<u32> = BitVector::with_fill(5000, true);
+        for i in 0 .. 2048 {
+            let start = bits.bit_len();
+            bits.resize(start + 99, false);
+            bits.push_bit(i % 3 == 0);
+        }
+        let start = bits.bit_len();
+        bits.resize(start + 3000, true);
+
+        let darray = DArray::new(bits.clone());
+        assert!(!darray.positions.is_empty());
+        assert!(!darray.subsamples.is_empty());
+        assert!(check_against_bin_search(bits));
+    }
+
+    #[test]
+    fn empty() {
+        let darray = DArray::new(BitVector::<u64>::with_fill(1000, false));
+        assert_eq!(0, darray.count_ones());
+        assert_eq!(None, darray.select1(0));
+    }
+}
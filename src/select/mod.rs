@@ -5,3 +5,9 @@ pub use self::bin_search::*;
 
 mod traits;
 pub use self::traits::*;
+
+mod sparse;
+pub use self::sparse::*;
+
+mod darray;
+pub use self::darray::*;
@@ -0,0 +1,237 @@
+use std::cmp;
+
+use bit_vec::{BitVecMut, BitVector};
+use int_vec::{IntVec, IntVector};
+use rank::{BitRankSupport, Rank9Select, RankSupport};
+use select::{Select0Support, Select1Support};
+use space_usage::SpaceUsage;
+use storage::BlockType;
+
+/// A sparse bit vector that stores the positions of its 1s.
+///
+/// The positions are Elias–Fano coded: the low `lg (universe / ones)`
+/// bits of each are stored verbatim in an `IntVector`, and the high
+/// bits are stored in unary in a bit vector with select support. This
+/// takes about `2 + lg (universe / ones)` bits per 1, regardless of the
+/// universe size, which makes it much smaller than a plain bit vector
+/// with rank support when fewer than a few percent of the bits are set.
+///
+/// Select is constant time, and rank is a select plus a short scan.
+///
+/// Construct with `SArray::from_ones`.
+#[derive(Clone, Debug)]
+pub struct SArray {
+    universe: u64,
+    ones: u64,
+    low_bits: usize,
+    lows: IntVector<u64>,
+    highs: Rank9Select<BitVector<u64>>,
+}
+
+impl SArray {
+    /// Creates a sparse bit vector of `universe` bits with the 1s at the
+    /// given positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `positions` is not strictly increasing or contains a
+    /// position `>= universe`.
+    pub fn from_ones(positions: &[u64], universe: u64) -> Self {
+        let ones = positions.len() as u64;
+        let low_bits = if ones == 0 || universe <= ones {
+            0
+        } else {
+            (universe / ones).floor_lg()
+        };
+
+        let mut lows = IntVector::with_capacity(cmp::max(1, low_bits),
+                                                ones);
+        let mut highs =
+            BitVector::with_fill(ones + (universe >> low_bits) + 1, false);
+
+        let mut previous = None;
+        for (i, &position) in positions.iter().enumerate() {
+            assert!(position < universe,
+                    "SArray::from_ones: position out of bounds");
+            assert!(previous < Some(position),
+                    "SArray::from_ones: positions not strictly increasing");
+            previous = Some(position);
+
+            if low_bits > 0 {
+                lows.push(position & u64::low_mask(low_bits));
+            }
+            highs.set_bit((position >> low_bits) + i as u64, true);
+        }
+
+        SArray {
+            universe,
+            ones,
+            low_bits,
+            lows,
+            highs: Rank9Select::new(highs),
+        }
+    }
+
+    /// The number of bits in the vector.
+    pub fn len(&self) -> u64 {
+        self.universe
+    }
+
+    /// Is the vector empty?
+    pub fn is_empty(&self) -> bool {
+        self.universe == 0
+    }
+
+    /// The number of 1 bits.
+    pub fn count_ones(&self) -> u64 {
+        self.ones
+    }
+
+    /// Returns the bit at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position >= self.len()`.
+    pub fn get_bit(&self, position: u64) -> bool {
+        let rank = self.rank1(position);
+        rank > 0 && self.select1(rank - 1) == Some(position)
+    }
+
+    fn low(&self, index: u64) -> u64 {
+        if self.low_bits == 0 {0} else {self.lows.get(index)}
+    }
+
+    // The number of 1s whose high part is less than `high`.
+    fn count_below_high(&self, high: u64) -> u64 {
+        if high == 0 {
+            0
+        } else {
+            self.highs.select0(high - 1)
+                .expect("SArray: high part out of range") + 1 - high
+        }
+    }
+}
+
+impl RankSupport for SArray {
+    type Over = bool;
+
+    fn rank(&self, position: u64, value: bool) -> u64 {
+        if value {self.rank1(position)} else {self.rank0(position)}
+    }
+
+    fn limit(&self) -> u64 {
+        self.universe
+    }
+}
+
+impl BitRankSupport for SArray {
+    fn rank1(&self, position: u64) -> u64 {
+        assert!(position < self.universe, "SArray::rank1: out of bounds");
+
+        let high = position >> self.low_bits;
+        let low = position & u64::low_mask(self.low_bits);
+
+        // The 1s with the same high part are consecutive and sorted by
+        // their low parts.
+        let start = self.count_below_high(high);
+        let limit = self.count_below_high(high + 1);
+
+        (start .. limit).find(|&i| self.low(i) > low).unwrap_or(limit)
+    }
+}
+
+impl Select1Support for SArray {
+    fn select1(&self, index: u64) -> Option<u64> {
+        if index >= self.ones { return None; }
+
+        let high = self.highs.select1(index)
+                       .expect("SArray::select1: missing high part")
+                   - index;
+        Some(high << self.low_bits | self.low(index))
+    }
+}
+
+impl SpaceUsage for SArray {
+    fn is_stack_only() -> bool { false }
+
+    fn heap_bytes(&self) -> usize {
+        self.lows.heap_bytes() + self.highs.heap_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::BitVec;
+    use quickcheck::quickcheck;
+
+    fn sarray_prop(mut positions: Vec<u16>, extra: u16) -> bool {
+        positions.sort();
+        positions.dedup();
+        let positions: Vec<u64> =
+            positions.into_iter().map(|p| p as u64).collect();
+        let universe = positions.last().map_or(0, |&p| p + 1)
+                     + extra as u64 % 100;
+
+        let sarray = SArray::from_ones(&positions, universe);
+
+        let mut rank = 0;
+        (0 .. universe).all(|i| {
+            let bit = positions.get(rank as usize) == Some(&i);
+            if bit { rank += 1; }
+            sarray.get_bit(i) == bit && sarray.rank1(i) == rank
+        }) && (0 .. positions.len() as u64 + 1).all(|i| {
+            sarray.select1(i) == positions.get(i as usize).cloned()
+        })
+    }
+
+    #[test]
+    fn rank_select_qc() {
+        quickcheck(sarray_prop as fn(Vec<u16>, u16) -> bool);
+    }
+
+    #[test]
+    fn dense() {
+        let positions: Vec<u64> = (0 .. 100).collect();
+        let sarray = SArray::from_ones(&positions, 100);
+
+        for i in 0 .. 100 {
+            assert_eq!(i + 1, sarray.rank1(i));
+            assert_eq!(Some(i), sarray.select1(i));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let sarray = SArray::from_ones(&[], 1000);
+        assert_eq!(1000, sarray.len());
+        assert_eq!(0, sarray.rank1(999));
+        assert_eq!(None, sarray.select1(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        SArray::from_ones(&[5, 3], 10);
+    }
+
+    #[test]
+    fn space() {
+        let universe = 1_000_000;
+        let positions: Vec<u64> = (0 .. 5_000).map(|i| i * 199 + i % 7)
+                                              .collect();
+
+        let sarray = SArray::from_ones(&positions, universe);
+        let bits: BitVector<u64> =
+            BitVector::from_ones(positions.iter().cloned(), universe);
+        let dense = Rank9Select::new(bits);
+
+        assert_eq!(dense.bit_len(), sarray.len());
+        assert!(sarray.heap_bytes() * 10 < dense.heap_bytes());
+
+        for (i, &position) in positions.iter().enumerate() {
+            assert_eq!(Some(position), sarray.select1(i as u64));
+            assert_eq!(dense.rank1(position), sarray.rank1(position));
+        }
+    }
+}
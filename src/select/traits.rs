@@ -1,4 +1,7 @@
 /// Supports selecting for 1 bits.
+///
+/// This trait is object safe, so a `Box<dyn Select1Support>` can hold
+/// any of the select structures.
 pub trait Select1Support {
     /// Returns the position of the `index`th 1 bit.
     fn select1(&self, index: u64) -> Option<u64>;
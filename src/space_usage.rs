@@ -67,6 +67,32 @@ pub trait SpaceUsage: Sized {
     /// that heap allocate varying amounts of memory.
     #[inline]
     fn heap_bytes(&self) -> usize;
+
+    /// Describes the size of the receiver for humans, as in
+    /// `"1.2 MiB (heap: 1.1 MiB)"`.
+    fn format_space(&self) -> String {
+        format!("{} (heap: {})",
+                format_bytes(self.total_bytes()),
+                format_bytes(self.heap_bytes()))
+    }
+}
+
+// Formats a number of bytes using binary prefixes.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
 }
 
 impl_stack_only_space_usage!(());
@@ -74,11 +100,13 @@ impl_stack_only_space_usage!(u8);
 impl_stack_only_space_usage!(u16);
 impl_stack_only_space_usage!(u32);
 impl_stack_only_space_usage!(u64);
+impl_stack_only_space_usage!(u128);
 impl_stack_only_space_usage!(usize);
 impl_stack_only_space_usage!(i8);
 impl_stack_only_space_usage!(i16);
 impl_stack_only_space_usage!(i32);
 impl_stack_only_space_usage!(i64);
+impl_stack_only_space_usage!(i128);
 impl_stack_only_space_usage!(isize);
 impl_stack_only_space_usage!(f32);
 impl_stack_only_space_usage!(f64);
@@ -184,6 +212,22 @@ mod test {
         assert_eq!(12, (0u32, 0u8, 0u32).total_bytes());
     }
 
+    #[test]
+    fn succinct_tuple_size() {
+        use bit_vec::BitVector;
+        use int_vec::IntVector;
+
+        let bv: BitVector<u64> = BitVector::with_fill(1000, true);
+        let iv: IntVector<u32> = IntVector::with_fill(7, 100, 5);
+        let heap = bv.heap_bytes() + iv.heap_bytes();
+
+        let pair = (bv, iv);
+        assert!(! <(BitVector, IntVector)>::is_stack_only());
+        assert_eq!(heap, pair.heap_bytes());
+        assert_eq!(size_of::<(BitVector<u64>, IntVector<u32>)>() + heap,
+                   pair.total_bytes());
+    }
+
     #[test]
     fn vec_size() {
         let v = Vec::<u64>::with_capacity(8);
@@ -203,4 +247,34 @@ mod test {
                       size_of::<Vec<Vec<u64>>>(),
                    w.total_bytes());
     }
+
+    #[test]
+    fn vector_total_bytes() {
+        use bit_vec::BitVector;
+        use int_vec::IntVector;
+
+        let bv: BitVector<u64> = BitVector::with_fill(1000, true);
+        assert_eq!(16 * 8, bv.heap_bytes());
+        assert_eq!(size_of::<BitVector<u64>>() + 16 * 8, bv.total_bytes());
+
+        let iv: IntVector<u32> = IntVector::with_fill(7, 100, 5);
+        assert_eq!(22 * 4, iv.heap_bytes());
+        assert_eq!(size_of::<IntVector<u32>>() + 22 * 4, iv.total_bytes());
+    }
+
+    #[test]
+    fn format_space() {
+        assert_eq!("0 B", format_bytes(0));
+        assert_eq!("1023 B", format_bytes(1023));
+        assert_eq!("1.0 KiB", format_bytes(1024));
+        assert_eq!("1.5 KiB", format_bytes(1536));
+        assert_eq!("1.2 MiB", format_bytes(1_258_291));
+        assert_eq!("3.0 GiB", format_bytes(3 << 30));
+
+        let vec = vec![0u64; 1000];
+        assert_eq!(format!("{} (heap: 7.8 KiB)",
+                           format_bytes(8000 + size_of::<Vec<u64>>())),
+                   vec.format_space());
+        assert_eq!("4 B (heap: 0 B)", 0u32.format_space());
+    }
 }
@@ -8,6 +8,7 @@ use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use num_traits::{One, PrimInt, ToPrimitive};
 
 use bit_vec::{BitVec, BitVecMut};
+use broadword;
 use rank::{BitRankSupport, RankSupport};
 use space_usage::SpaceUsage;
 
@@ -17,7 +18,7 @@ use space_usage::SpaceUsage;
 ///
 ///   - methods for computing sizes and offsets relative to the block size,
 ///   - methods for getting and setting individual and groups of bits,
-///   - a method for computing rank,
+///   - methods for computing rank and select within a block,
 ///   - three arithmetic methods that probably belong elsewhere, and
 ///   - block-based, endian-specified I/O.
 pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
@@ -204,6 +205,15 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         (self & !mask) | (shifted_value & mask)
     }
 
+    /// Finds the index of the `rank`th one bit (0-based) in the block.
+    ///
+    /// Returns `None` if the block has `rank` or fewer one bits. The
+    /// default widens the block to `u64` for `broadword::select1`.
+    #[inline]
+    fn select1(self, rank: usize) -> Option<usize> {
+        broadword::select1(rank, self.to_u64().unwrap())
+    }
+
     // Arithmetic methods that probably belong elsewhere.
 
     /// Returns the smallest number `n` such that `2.pow(n) >= self`.
@@ -220,6 +230,16 @@ pub trait BlockType: PrimInt + BitVec + BitVecMut + BitRankSupport +
         Self::nbits() - 1 - self.leading_zeros() as usize
     }
 
+    /// Counts the 1 bits in the block.
+    ///
+    /// Uses [`broadword::popcount`](../broadword/fn.popcount.html) a
+    /// 64-bit word at a time, so the implementation is chosen at compile
+    /// time.
+    #[inline]
+    fn popcount(self) -> usize {
+        broadword::popcount(self.to_u64().unwrap())
+    }
+
     /// Returns the smallest number `n` such that `n * divisor >= self`.
     #[inline]
     fn ceil_div(self, divisor: Self) -> Self {
@@ -298,6 +318,43 @@ impl_block_type!(u16, read_u16, write_u16);
 impl_block_type!(u32, read_u32, write_u32);
 impl_block_type!(u64, read_u64, write_u64);
 
+impl BlockType for u128 {
+    fn read_block<R, T>(source: &mut R) -> io::Result<Self>
+        where R: io::Read,
+              T: ByteOrder {
+        source.read_u128::<T>()
+    }
+
+    fn write_block<W, T>(&self, sink: &mut W) -> io::Result<()>
+        where W: io::Write,
+              T: ByteOrder {
+        sink.write_u128::<T>(*self)
+    }
+
+    // The defaults would truncate to `u64`, so count and select in each
+    // half.
+    #[inline]
+    fn popcount(self) -> usize {
+        broadword::popcount(self as u64)
+            + broadword::popcount((self >> 64) as u64)
+    }
+
+    #[inline]
+    fn select1(self, rank: usize) -> Option<usize> {
+        let low = self as u64;
+        let low_ones = low.count_ones() as usize;
+
+        if rank < low_ones {
+            broadword::select1(rank, low)
+        } else {
+            broadword::select1(rank - low_ones, (self >> 64) as u64)
+                .map(|index| index + 64)
+        }
+    }
+
+    fn_low_mask!(u128);
+}
+
 impl BlockType for usize {
     #[cfg(target_pointer_width = "64")]
     fn read_block<R, T>(source: &mut R) -> io::Result<Self>
@@ -377,6 +434,10 @@ mod test {
         assert_eq!(0b00011111, u8::low_mask(5));
         assert_eq!(0b0011111111111111, u16::low_mask(14));
         assert_eq!(0b1111111111111111, u16::low_mask(16));
+        assert_eq!(0, u128::low_mask(0));
+        assert_eq!(0xFFFF_FFFF_FFFF_FFFF, u128::low_mask(64));
+        assert_eq!((1 << 100) - 1, u128::low_mask(100));
+        assert_eq!(!0, u128::low_mask(128));
     }
 
     #[test]
@@ -400,6 +461,13 @@ mod test {
                    0b0100110001110000u16.get_bits(0, 5));
         assert_eq!(0b0100110001110000,
                    0b0100110001110000u16.get_bits(0, 16));
+
+        let block = 0xDEAD_BEEF_u128 << 60 | 0b1011;
+        assert_eq!(0xDEAD_BEEF, block.get_bits(60, 32));
+        assert_eq!(0xDEAD_BEEF << 4, block.get_bits(56, 36));
+        assert_eq!(0b1011, block.get_bits(0, 60));
+        assert_eq!(block, block.get_bits(0, 128));
+        assert_eq!(0, block.get_bits(92, 36));
     }
 
     #[test]
@@ -414,6 +482,13 @@ mod test {
                    0b0110001111000001u16.with_bits(0, 8, 0b10101010));
         assert_eq!(0b0000000000000010,
                    0b0110001111000001u16.with_bits(0, 16, 0b10));
+
+        assert_eq!(0xABC << 62 | 1,
+                   1u128.with_bits(62, 12, 0xABC));
+        assert_eq!(!0 << 127,
+                   0u128.with_bits(127, 1, 1));
+        assert_eq!(0b10,
+                   (!0u128).with_bits(0, 128, 0b10));
     }
 
     #[test]
@@ -504,5 +579,85 @@ mod test {
 
         quickcheck(prop as fn(u64, u64) -> TestResult);
     }
-}
 
+    #[test]
+    fn select1() {
+        assert_eq!(Some(0), 0b1011u8.select1(0));
+        assert_eq!(Some(3), 0b1011u8.select1(2));
+        assert_eq!(None, 0b1011u8.select1(3));
+        assert_eq!(Some(7), 0xFFu8.select1(7));
+
+        assert_eq!(Some(4), 0x8010u16.select1(0));
+        assert_eq!(Some(15), 0x8010u16.select1(1));
+        assert_eq!(None, 0u16.select1(0));
+
+        assert_eq!(Some(0), 0xFFFF_FFFFu32.select1(0));
+        assert_eq!(Some(31), 0xFFFF_FFFFu32.select1(31));
+        assert_eq!(None, 0xFFFF_FFFFu32.select1(32));
+
+        assert_eq!(Some(63), (1u64 << 63).select1(0));
+        assert_eq!(Some(63), (!0u64).select1(63));
+
+        assert_eq!(Some(127), (1u128 << 127).select1(0));
+        assert_eq!(Some(64), (1u128 << 64 | 1).select1(1));
+        assert_eq!(Some(100), (!0u128).select1(100));
+        assert_eq!(None, (!0u128).select1(128));
+    }
+
+    #[test]
+    fn read_write_u128() {
+        use byteorder::{BigEndian, LittleEndian};
+
+        let block = 0x0123_4567_89AB_CDEF_u128 << 64 | 0xFEDC_BA98;
+
+        let mut bytes = Vec::new();
+        block.write_block::<_, BigEndian>(&mut bytes).unwrap();
+        block.write_block::<_, LittleEndian>(&mut bytes).unwrap();
+        assert_eq!(32, bytes.len());
+        assert_eq!(&[0x01, 0x23], &bytes[.. 2]);
+        assert_eq!(&[0x98, 0xBA], &bytes[16 .. 18]);
+
+        let mut source = &bytes[..];
+        assert_eq!(block,
+                   u128::read_block::<_, BigEndian>(&mut source).unwrap());
+        assert_eq!(block,
+                   u128::read_block::<_, LittleEndian>(&mut source).unwrap());
+    }
+
+    #[test]
+    fn select1_qc() {
+        fn prop(block: u32, rank: u8) -> bool {
+            let rank = rank as usize % 33;
+            let expected = (0 .. 32).filter(|&i| block.get_bit(i))
+                                    .nth(rank);
+            block.select1(rank) == expected
+        }
+
+        quickcheck(prop as fn(u32, u8) -> bool);
+    }
+
+    #[test]
+    fn popcount_qc() {
+        // `broadword::count_ones` is the path taken on targets without
+        // a native popcount; check it agrees with `count_ones()` for
+        // every block type, whichever path `popcount` takes here.
+        fn prop(low: u64, high: u64) -> bool {
+            let wide = (high as u128) << 64 | low as u128;
+            let forced = broadword::count_ones(low)
+                + broadword::count_ones(high);
+
+            forced == wide.count_ones() as usize
+                && wide.popcount() == forced
+                && low.popcount() == broadword::count_ones(low)
+                && (low as u32).popcount()
+                    == broadword::count_ones(low & 0xFFFF_FFFF)
+                && (low as u16).popcount()
+                    == broadword::count_ones(low & 0xFFFF)
+                && (low as u8).popcount() == broadword::count_ones(low & 0xFF)
+                && (low as usize).popcount() == (low as usize).count_ones()
+                                                    as usize
+        }
+
+        quickcheck(prop as fn(u64, u64) -> bool);
+    }
+}
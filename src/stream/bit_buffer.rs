@@ -57,6 +57,31 @@ impl<Inner: BitVec> BitBuffer<Inner> {
                            "position out of bounds"))
         }
     }
+
+    /// Moves the position forward to the next byte boundary, skipping
+    /// the bits before it.
+    ///
+    /// Fails, without moving, if the boundary is past the end of the
+    /// buffer. When writing, use `pad_to_byte` instead.
+    pub fn align_to_byte(&mut self) -> Result<()> {
+        let target = self.pos.ceil_div(8) * 8;
+        self.seek(target)
+    }
+}
+
+impl<Inner: BitVecPush> BitBuffer<Inner> {
+    /// Moves the position forward to the next byte boundary, padding
+    /// the buffer with 0s if the boundary is past its end.
+    ///
+    /// Use this when writing; to align while reading, without changing
+    /// the data, use `align_to_byte`.
+    pub fn pad_to_byte(&mut self) {
+        let target = self.pos.ceil_div(8) * 8;
+        while self.data.bit_len() < target {
+            self.data.push_bit(false);
+        }
+        self.pos = target;
+    }
 }
 
 impl<Inner> BitBuffer<Inner> {
@@ -175,4 +200,56 @@ mod test {
         assert_eq!(Some(true), vec.pop_bit());
         assert_eq!(None, vec.pop_bit());
     }
+
+    #[test]
+    fn align_to_byte() {
+        let mut writer: BitBuffer<BitVector<u8>> = BitBuffer::new();
+
+        writer.write_int(3, 5u8).unwrap();
+        writer.pad_to_byte();
+        assert_eq!(8, writer.position());
+        writer.pad_to_byte();
+        assert_eq!(8, writer.position());
+        writer.write_int(8, 0xA7u8).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.pad_to_byte();
+        assert_eq!(24, writer.position());
+        assert_eq!(24, writer.bit_len());
+        writer.write_int(5, 17u8).unwrap();
+
+        let mut reader = BitBuffer::from(writer.into_inner());
+        assert_eq!(Some(5), reader.read_int(3).unwrap());
+        reader.align_to_byte().unwrap();
+        assert_eq!(Some(0xA7), reader.read_int(8).unwrap());
+        assert_eq!(Some(true), reader.read_bit().unwrap());
+        reader.align_to_byte().unwrap();
+        assert_eq!(24, reader.position());
+        assert_eq!(Some(17), reader.read_int(5).unwrap());
+        assert_eq!(29, reader.position());
+
+        // The next boundary, 32, is past the end.
+        assert!(reader.align_to_byte().is_err());
+        assert_eq!(29, reader.position());
+        assert_eq!(29, reader.bit_len());
+
+        reader.seek(8).unwrap();
+        assert_eq!(Some(0xA7), reader.read_int(8).unwrap());
+        assert!(reader.seek(30).is_err());
+        assert_eq!(16, reader.position());
+    }
+
+    #[test]
+    fn align_to_byte_slice() {
+        let vec = BitVector::<u8>::block_with_fill(3, 0b0101_1010);
+        let mut reader = BitBuffer::from(BitSlice::new(&vec, 4 ..));
+
+        assert_eq!(Some(0b0101u8), reader.read_int(4).unwrap());
+        reader.align_to_byte().unwrap();
+        assert_eq!(8, reader.position());
+        assert_eq!(Some(0b1010_0101u8), reader.read_int(8).unwrap());
+        reader.align_to_byte().unwrap();
+        assert_eq!(16, reader.position());
+        assert_eq!(Some(0b0101u8), reader.read_int(4).unwrap());
+        assert!(reader.align_to_byte().is_err());
+    }
 }
@@ -0,0 +1,166 @@
+use std::cmp;
+use std::io::Result;
+
+use internal::errors::*;
+use stream::BitRead;
+
+/// A bit reader decodes bits from a borrowed byte slice.
+///
+/// Bits are read least-significant first within each byte, which is the
+/// layout of a `BitVector<u8>`. Unlike a `BitBuffer`, the reader does
+/// not need to own its data, so it can decode directly from, say, a
+/// memory-mapped file.
+#[derive(Clone, Debug)]
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    pos: u64,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a new bit reader positioned at the start of `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            pos: 0,
+        }
+    }
+
+    /// The position in the slice, in bits, where the next read will
+    /// occur.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// The number of bits left to read.
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.data.len() as u64 * 8 - self.pos
+    }
+
+    /// Returns the byte slice underlying the reader.
+    #[inline]
+    pub fn into_inner(self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Reads `nbits` bits as an integer, least-significant bit first.
+    ///
+    /// This reads up to a byte at a time, so it is faster than the
+    /// bit-at-a-time `BitRead::read_int`. End-of-file before any bits
+    /// are read is indicated by `Ok(None)`; running out partway through
+    /// is an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nbits` is greater than 64.
+    pub fn read_bits(&mut self, nbits: usize) -> Result<Option<u64>> {
+        assert!(nbits <= 64, "BitReader::read_bits: too many bits");
+
+        if nbits == 0 {
+            return Ok(Some(0));
+        } else if self.remaining() == 0 {
+            return Ok(None);
+        } else if self.remaining() < nbits as u64 {
+            self.pos = self.data.len() as u64 * 8;
+            return out_of_bits("BitReader::read_bits");
+        }
+
+        let mut result = 0;
+        let mut filled = 0;
+
+        while filled < nbits {
+            let byte = self.data[(self.pos / 8) as usize];
+            let offset = (self.pos & 7) as usize;
+            let count = cmp::min(8 - offset, nbits - filled);
+
+            let chunk = (byte >> offset) as u64 & ((1 << count) - 1);
+            result |= chunk << filled;
+
+            filled += count;
+            self.pos += count as u64;
+        }
+
+        Ok(Some(result))
+    }
+}
+
+impl<'a> BitRead for BitReader<'a> {
+    fn read_bit(&mut self) -> Result<Option<bool>> {
+        if self.remaining() == 0 {
+            Ok(None)
+        } else {
+            let byte = self.data[(self.pos / 8) as usize];
+            let result = byte >> (self.pos & 7) & 1 == 1;
+            self.pos += 1;
+            Ok(Some(result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bit_vec::{BitVec, BitVector};
+    use coding::*;
+    use stream::{BitBuffer, BitRead, BitWrite};
+
+    fn to_bytes(bits: &BitVector<u8>) -> Vec<u8> {
+        (0 .. bits.block_len()).map(|i| bits.get_block(i)).collect()
+    }
+
+    #[test]
+    fn read_bit() {
+        let bytes = [0b1010_0001u8];
+        let mut reader = BitReader::new(&bytes);
+
+        let bits: Vec<bool> = (0 .. 8)
+            .map(|_| reader.read_bit().unwrap().unwrap())
+            .collect();
+        assert_eq!(vec![true, false, false, false, false, true, false, true],
+                   bits);
+        assert_eq!(None, reader.read_bit().unwrap());
+    }
+
+    #[test]
+    fn read_bits() {
+        let mut writer: BitBuffer<BitVector<u8>> = BitBuffer::new();
+        writer.write_int(3, 5u8).unwrap();
+        writer.write_int(64, 0xDEAD_BEEF_0123_4567u64).unwrap();
+        writer.write_int(13, 4000u16).unwrap();
+        writer.write_int(1, 1u8).unwrap();
+
+        let bytes = to_bytes(writer.inner());
+        let mut reader = BitReader::new(&bytes);
+
+        assert_eq!(Some(5), reader.read_bits(3).unwrap());
+        assert_eq!(Some(0xDEAD_BEEF_0123_4567), reader.read_bits(64).unwrap());
+        assert_eq!(Some(4000), reader.read_bits(13).unwrap());
+        assert_eq!(Some(1), reader.read_bits(1).unwrap());
+        assert_eq!(81, reader.position());
+
+        // The remaining seven bits are padding.
+        assert_eq!(7, reader.remaining());
+        assert!(reader.read_bits(8).is_err());
+        assert_eq!(None, reader.read_bits(1).unwrap());
+    }
+
+    #[test]
+    fn decode_codes() {
+        let values = [1u64, 2, 17, 1000, 3, 1 << 40];
+
+        let mut writer: BitBuffer<BitVector<u8>> = BitBuffer::new();
+        for &value in &values {
+            GAMMA.encode(&mut writer, value).unwrap();
+            DELTA.encode(&mut writer, value).unwrap();
+        }
+
+        let bytes = to_bytes(writer.inner());
+        let mut reader = BitReader::new(&bytes);
+
+        for &value in &values {
+            assert_eq!(Some(value), GAMMA.decode(&mut reader).unwrap());
+            assert_eq!(Some(value), DELTA.decode(&mut reader).unwrap());
+        }
+    }
+}
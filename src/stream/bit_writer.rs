@@ -0,0 +1,124 @@
+use std::io::{Result, Write};
+
+use stream::BitWrite;
+
+/// A bit writer encodes bits into bytes for an `io::Write`.
+///
+/// Bits are packed least-significant first within each byte, which is
+/// the layout that `BitReader` reads. Each byte is written to the
+/// underlying writer as soon as it fills, so when writing to a file or
+/// socket, wrap it in an `io::BufWriter`.
+///
+/// A final partial byte is only written by `finish`, which pads it with
+/// 0s. Dropping the bit writer without calling `finish` loses it.
+#[derive(Debug)]
+pub struct BitWriter<W: Write> {
+    inner: W,
+    byte: u8,
+    pos: u64,
+}
+
+impl<W: Write> BitWriter<W> {
+    /// Creates a new bit writer that writes to `inner`.
+    pub fn new(inner: W) -> Self {
+        BitWriter {
+            inner,
+            byte: 0,
+            pos: 0,
+        }
+    }
+
+    /// The number of bits written so far.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Borrows the underlying writer.
+    ///
+    /// Bits in the current partial byte have not been written to it yet.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Pads the last byte with 0s, writes it, flushes, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        if self.pos & 7 != 0 {
+            try!(self.inner.write_all(&[self.byte]));
+        }
+
+        try!(self.inner.flush());
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> BitWrite for BitWriter<W> {
+    fn write_bit(&mut self, value: bool) -> Result<()> {
+        let offset = self.pos & 7;
+        if value {
+            self.byte |= 1 << offset;
+        }
+
+        if offset == 7 {
+            try!(self.inner.write_all(&[self.byte]));
+            self.byte = 0;
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use coding::*;
+    use storage::BlockType;
+    use stream::{BitRead, BitReader};
+
+    #[test]
+    fn write_bit() {
+        let mut writer = BitWriter::new(Vec::new());
+        for &bit in &[true, false, false, false, false, true, false, true,
+                      true, true] {
+            writer.write_bit(bit).unwrap();
+        }
+
+        assert_eq!(10, writer.position());
+        assert_eq!(&[0b1010_0001], &writer.get_ref()[..]);
+        assert_eq!(vec![0b1010_0001, 0b0000_0011], writer.finish().unwrap());
+    }
+
+    #[test]
+    fn finish_aligned() {
+        let mut writer = BitWriter::new(Vec::new());
+        writer.write_int(16, 0xBEEFu16).unwrap();
+        assert_eq!(vec![0xEF, 0xBE], writer.finish().unwrap());
+
+        assert!(BitWriter::new(Vec::new()).finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn encode_gamma() {
+        let values: Vec<u64> = (1 .. 200).map(|i| i * i * 37).collect();
+
+        let mut writer = BitWriter::new(Vec::new());
+        let bits = GAMMA.encode_all(&mut writer, &values).unwrap();
+        assert_eq!(bits as u64, writer.position());
+
+        let bytes = writer.finish().unwrap();
+        assert_eq!(bits.ceil_div(8), bytes.len());
+
+        let mut reader = BitReader::new(&bytes);
+        for &value in &values {
+            assert_eq!(Some(value), GAMMA.decode(&mut reader).unwrap());
+        }
+
+        // Only padding remains.
+        assert!(reader.remaining() < 8);
+        while let Some(bit) = reader.read_bit().unwrap() {
+            assert!(!bit);
+        }
+    }
+}
@@ -5,3 +5,9 @@ pub use self::traits::*;
 
 mod bit_buffer;
 pub use self::bit_buffer::*;
+
+mod bit_reader;
+pub use self::bit_reader::*;
+
+mod bit_writer;
+pub use self::bit_writer::*;